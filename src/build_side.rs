@@ -0,0 +1,39 @@
+use std::{fmt, str::FromStr};
+
+use crate::JoinerError;
+
+/// Which relation a hash join builds its table from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum BuildSide {
+    /// The relation with fewer blocks.
+    #[default]
+    Auto,
+    Left,
+    Right,
+}
+
+impl FromStr for BuildSide {
+    type Err = JoinerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "auto" => Ok(BuildSide::Auto),
+            "left" => Ok(BuildSide::Left),
+            "right" => Ok(BuildSide::Right),
+            _ => Err(JoinerError::Input(format!(
+                "unknown build side '{s}', expected one of: left, right, auto"
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for BuildSide {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            BuildSide::Auto => "auto",
+            BuildSide::Left => "left",
+            BuildSide::Right => "right",
+        };
+        f.pad(name)
+    }
+}
@@ -0,0 +1,184 @@
+use std::slice;
+
+use crate::{validation_issues, Column, JoinerError, Table};
+
+/// Builds a `Table` one call at a time instead of spelling out every field.
+///
+/// Column settings such as `indexed` apply to the column added last, and a
+/// column without `distinct` has as many distinct values as the table has rows.
+///
+/// ```
+/// use joiner::TableBuilder;
+///
+/// let customer = TableBuilder::new("Customer")
+///     .column("id").indexed()
+///     .column("city").distinct(40)
+///     .rows(200)
+///     .blocks(150)
+///     .sorted_on("id")
+///     .build()
+///     .unwrap();
+/// assert_eq!(customer.sorted_column.as_ref(), Some(&customer.columns[0]));
+/// assert_eq!(customer.columns[0].total_values, 200);
+/// ```
+#[derive(Debug, Clone)]
+pub struct TableBuilder {
+    name: String,
+    /// Every column with its distinct count, if one was given.
+    columns: Vec<(Column, Option<u32>)>,
+    nr: u32,
+    br: u32,
+    shards: u32,
+    memory_hint: Option<u32>,
+    sorted_on: Option<String>,
+    unsorted: bool,
+    intermediate: bool,
+    /// The first column setting made before any column was added.
+    misplaced: Option<&'static str>,
+}
+
+impl TableBuilder {
+    pub fn new(name: &str) -> Self {
+        TableBuilder {
+            name: name.to_string(),
+            columns: Vec::new(),
+            nr: 0,
+            br: 0,
+            shards: 1,
+            memory_hint: None,
+            sorted_on: None,
+            unsorted: false,
+            intermediate: false,
+            misplaced: None,
+        }
+    }
+
+    pub fn column(mut self, name: &str) -> Self {
+        self.columns.push((unindexed_column(name), None));
+        self
+    }
+
+    pub fn indexed(self) -> Self {
+        self.with_last_column("indexed", |column, _| column.indexed = true)
+    }
+
+    pub fn clustered(self) -> Self {
+        self.with_last_column("clustered", |column, _| column.clustered = true)
+    }
+
+    /// Makes the index a partial one over `coverage` of the rows.
+    pub fn index_coverage(self, coverage: f64) -> Self {
+        self.with_last_column("index_coverage", |column, _| column.index_coverage = Some(coverage))
+    }
+
+    pub fn distinct(self, total_values: u32) -> Self {
+        self.with_last_column("distinct", |_, distinct| *distinct = Some(total_values))
+    }
+
+    pub fn rows(mut self, nr: u32) -> Self {
+        self.nr = nr;
+        self
+    }
+
+    pub fn blocks(mut self, br: u32) -> Self {
+        self.br = br;
+        self
+    }
+
+    pub fn shards(mut self, shards: u32) -> Self {
+        self.shards = shards;
+        self
+    }
+
+    /// Memory in blocks to use when this table dominates a join; see `TableStats::memory_hint`.
+    pub fn memory_hint(mut self, blocks: u32) -> Self {
+        self.memory_hint = Some(blocks);
+        self
+    }
+
+    /// The column the table is sorted on, the first one added unless given or `unsorted`.
+    pub fn sorted_on(mut self, column: &str) -> Self {
+        self.sorted_on = Some(column.to_string());
+        self
+    }
+
+    /// Makes the table an unsorted heap instead of sorting it on a column.
+    pub fn unsorted(mut self) -> Self {
+        self.unsorted = true;
+        self
+    }
+
+    /// Marks the table as the result of another join, so its columns have no
+    /// indexes and it has no sort order; see `Table::is_intermediate`.
+    pub fn intermediate(mut self) -> Self {
+        self.intermediate = true;
+        self
+    }
+
+    fn with_last_column(mut self, setting: &'static str, f: impl FnOnce(&mut Column, &mut Option<u32>)) -> Self {
+        match self.columns.last_mut() {
+            Some((column, distinct)) => f(column, distinct),
+            None => {
+                self.misplaced.get_or_insert(setting);
+            }
+        }
+        self
+    }
+
+    /// The table, as long as it passes every check `validation_issues` makes.
+    pub fn build(self) -> Result<Table, JoinerError> {
+        if let Some(setting) = self.misplaced {
+            return Err(JoinerError::Input(format!(
+                "{setting} was set on table {} before any of its columns was added", self.name
+            )));
+        }
+        let columns: Vec<Column> = self
+            .columns
+            .into_iter()
+            .map(|(column, distinct)| Column { total_values: distinct.unwrap_or(self.nr), ..column })
+            .collect();
+        if columns.is_empty() && self.sorted_on.is_none() {
+            return Err(JoinerError::Input(format!("table {} has no columns", self.name)));
+        }
+        let sorted_column = match (&self.sorted_on, self.unsorted) {
+            (_, true) => None,
+            // A column that wasn't added is reported by the validation below.
+            (Some(name), false) => {
+                Some(columns.iter().find(|x| x.name == *name).cloned().unwrap_or_else(|| unindexed_column(name)))
+            }
+            (None, false) => columns.first().cloned(),
+        };
+
+        let table = Table {
+            name: self.name,
+            columns,
+            sorted_column,
+            nr: self.nr,
+            br: self.br,
+            tuple_size: None,
+            block_size: None,
+            shards: self.shards,
+            memory_hint: self.memory_hint,
+            is_intermediate: self.intermediate,
+        };
+        match validation_issues(slice::from_ref(&table)).into_iter().next() {
+            Some(issue) => Err(JoinerError::Input(issue.to_string())),
+            None => Ok(table),
+        }
+    }
+}
+
+fn unindexed_column(name: &str) -> Column {
+    Column {
+        name: name.to_string(),
+        indexed: false,
+        clustered: false,
+        total_values: 0,
+        data_type: None,
+        index_group: None,
+        histogram: Vec::new(),
+        index_type: None,
+        null_count: 0,
+        index_coverage: None,
+    }
+}
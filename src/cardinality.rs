@@ -0,0 +1,127 @@
+use std::cmp;
+
+use crate::{Column, Comparison, JoinType, Table};
+
+/// Fraction of the cross product a range comparison such as `<` keeps when
+/// nothing is known about how the values of its two columns overlap.
+pub const RANGE_SELECTIVITY: f64 = 1.0 / 3.0;
+
+/*
+ * Fraction of the cross product that satisfies column1 = column2, assuming
+ * every value of the side with fewer distinct values appears on the other:
+ * 1 / max(V(column1), V(column2)). A column without values matches nothing.
+ */
+pub fn selectivity(column1: &Column, column2: &Column) -> f64 {
+    if column1.total_values == 0 || column2.total_values == 0 {
+        return 0.0;
+    }
+
+    1.0 / cmp::max(column1.total_values, column2.total_values) as f64
+}
+
+/*
+ * Selectivity of column1 = column2 from equi-width histograms with the same
+ * n buckets over both columns. Within bucket i the f_i rows spread evenly
+ * over V / n distinct values, so the bucket contributes
+ * f1_i * f2_i / (max(V1, V2) / n) matches out of the F1 * F2 pairs:
+ * n * sum(f1_i * f2_i) / (max(V1, V2) * F1 * F2).
+ * Uniform histograms give back 1 / max(V1, V2). Without a histogram on
+ * both sides there's nothing to weigh, so this is `None`.
+ */
+pub fn histogram_selectivity(column1: &Column, column2: &Column) -> Option<f64> {
+    let (histogram1, histogram2) = (&column1.histogram, &column2.histogram);
+    if histogram1.is_empty() || histogram1.len() != histogram2.len() {
+        return None;
+    }
+    let total = |histogram: &[u32]| histogram.iter().map(|x| *x as f64).sum::<f64>();
+    let (total1, total2) = (total(histogram1), total(histogram2));
+    let distinct = cmp::max(column1.total_values, column2.total_values) as f64;
+    if total1 == 0.0 || total2 == 0.0 || distinct == 0.0 {
+        return Some(0.0);
+    }
+
+    let matches: f64 = histogram1.iter().zip(histogram2).map(|(f1, f2)| *f1 as f64 * *f2 as f64).sum();
+    Some(histogram1.len() as f64 * matches / (distinct * total1 * total2))
+}
+
+/*
+ * Fraction of the cross product that satisfies column1 op column2. Equality
+ * uses the histograms when both columns carry one and the distinct values
+ * otherwise, <> keeps what equality doesn't, and a range comparison such as
+ * < keeps range_selectivity of it, whatever the distinct values.
+ */
+pub fn comparison_selectivity(column1: &Column, op: Comparison, column2: &Column, range_selectivity: f64) -> f64 {
+    let equal = || histogram_selectivity(column1, column2).unwrap_or_else(|| selectivity(column1, column2));
+    match op {
+        Comparison::Eq => equal(),
+        Comparison::Ne => 1.0 - equal(),
+        Comparison::Lt | Comparison::Le | Comparison::Gt | Comparison::Ge => range_selectivity,
+    }
+}
+
+/*
+ * Estimated number of tuples produced by the join
+ * table1.column1 op table2.column2: nr1 * nr2 * comparison_selectivity,
+ * counting the rows of every shard, so nr1 * nr2 / max(V1, V2) for an
+ * equi-join and nr1 * nr2 / 3 for a range join. NULL compares to nothing,
+ * so only the nr - null_count rows with a value can match. Outer joins
+ * additionally keep the rows of the preserved side(s).
+ */
+pub fn estimate_join_cardinality(table1: &Table, column1: &Column, op: Comparison, table2: &Table, column2: &Column, join_type: JoinType) -> u64 {
+    estimate_filtered_join_cardinality(table1, table2, &[], &[(column1, op, column2)], RANGE_SELECTIVITY, join_type)
+}
+
+/*
+ * Conjunction of equalities on (column1_i, column2_i): the conditions are
+ * assumed independent, so their selectivities multiply.
+ */
+pub fn estimate_composite_join_cardinality(table1: &Table, table2: &Table, keys: &[(&Column, &Column)], join_type: JoinType) -> u64 {
+    estimate_filtered_join_cardinality(table1, table2, keys, &[], RANGE_SELECTIVITY, join_type)
+}
+
+/*
+ * Conjunction of the equalities in keys and the other comparisons in
+ * filters, such as those of a ResolvedJoin, each independent of the rest.
+ * Range comparisons keep range_selectivity of the pairs, RANGE_SELECTIVITY
+ * by default.
+ */
+pub fn estimate_filtered_join_cardinality(table1: &Table, table2: &Table, keys: &[(&Column, &Column)], filters: &[(&Column, Comparison, &Column)], range_selectivity: f64, join_type: JoinType) -> u64 {
+    let unmatched = match join_type {
+        JoinType::Inner => 0,
+        JoinType::LeftOuter => table1.total_rows(),
+        JoinType::RightOuter => table2.total_rows(),
+        JoinType::FullOuter => table1.total_rows() + table2.total_rows(),
+    };
+
+    let conditions: Vec<(&Column, Comparison, &Column)> =
+        keys.iter().map(|(column1, column2)| (*column1, Comparison::Eq, *column2)).chain(filters.iter().copied()).collect();
+    let mut cardinality = table1.total_rows() as f64 * table2.total_rows() as f64;
+    for (column1, op, column2) in &conditions {
+        cardinality *= comparison_selectivity(column1, *op, column2, range_selectivity);
+    }
+    // A row whose column is NULL fails every condition on it, but only once.
+    cardinality *= non_null_fraction(table1, conditions.iter().map(|(column1, _, _)| *column1));
+    cardinality *= non_null_fraction(table2, conditions.iter().map(|(_, _, column2)| *column2));
+
+    cardinality.round() as u64 + unmatched
+}
+
+/// Fraction of the rows of `table` where none of `columns` is NULL, counting
+/// a column named more than once a single time.
+fn non_null_fraction<'a>(table: &Table, columns: impl Iterator<Item = &'a Column>) -> f64 {
+    if table.nr == 0 {
+        return 1.0;
+    }
+
+    let mut seen: Vec<&str> = Vec::new();
+    let mut fraction = 1.0;
+    for column in columns {
+        if seen.contains(&column.name.as_str()) {
+            continue;
+        }
+        seen.push(&column.name);
+        fraction *= 1.0 - cmp::min(column.null_count, table.nr) as f64 / table.nr as f64;
+    }
+
+    fraction
+}
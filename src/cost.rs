@@ -0,0 +1,681 @@
+use log::debug;
+use std::cmp;
+
+use crate::{selectivity, BuildSide, Column, IndexType, Table, TableStats};
+
+/// Size of a disk block in bytes.
+pub const BLOCK_SIZE: u32 = 4096;
+/// Fewest memory blocks any join method can run in: one per input and one for the output.
+pub const MIN_MEMORY_BLOCKS: u32 = 3;
+/// Size of a single B+-tree entry in bytes: an 8-byte key plus an 8-byte pointer.
+pub const INDEX_ENTRY_SIZE: u32 = 16;
+/// Blocks a hash index reads to find a key's bucket, ignoring overflow chains.
+pub const HASH_INDEX_LOOKUP_COST: u32 = 1;
+/// Buffers block nested loop keeps for the inner relation and the output.
+pub const DEFAULT_RESERVED_BUFFERS: u32 = 2;
+/// Time to transfer one block from a spinning disk, seek included, in milliseconds.
+pub const DEFAULT_IO_LATENCY_MS: f64 = 10.0;
+
+/// Wall-clock time in milliseconds of transferring `blocks` blocks at `io_latency_ms` each.
+pub fn estimated_time_ms(blocks: u64, io_latency_ms: f64) -> f64 {
+    blocks as f64 * io_latency_ms
+}
+
+/// Number of children of a B+-tree node that fills one block of `block_size` bytes.
+pub fn default_index_fanout(block_size: u32) -> u32 {
+    block_size / INDEX_ENTRY_SIZE
+}
+
+/// Height of a B+-tree with `fanout` children per node indexing `k` distinct keys,
+/// ceil(log_fanout(k)), but at least one level.
+///
+/// Every node is assumed full, so this is the number of blocks one lookup
+/// reads from the root down to a leaf. `fanout` has to be at least 2.
+///
+/// ```
+/// use joiner::height_of_index_tree;
+///
+/// assert_eq!(height_of_index_tree(256, 1), 1);
+/// assert_eq!(height_of_index_tree(256, 200), 1);
+/// assert_eq!(height_of_index_tree(256, 65_536), 2);
+/// assert_eq!(height_of_index_tree(256, 65_537), 3);
+/// ```
+pub fn height_of_index_tree(fanout: u32, k: u32) -> u32 {
+    if k <= 1 {
+        return 1;
+    }
+    (k as f64).log(fanout as f64).ceil() as u32
+}
+
+/*
+ * Tuple-at-a-time nested loop: the inner relation is scanned once for
+ * every outer tuple, nr1 * br2 + br1.
+ */
+pub fn nested_loop_join_cost(table1: &impl TableStats, table2: &impl TableStats) -> u64 {
+    let cost = table1.nr() as u64 * table2.br() as u64 + table1.br() as u64;
+    debug!("nested loop: nr1 {} * br2 {} + br1 {} = {cost}", table1.nr(), table2.br(), table1.br());
+    cost
+}
+
+pub fn nested_loop_join_explain(table1: &Table, table2: &Table) -> String {
+    format!(
+        "Nested Loop: nr1 * br2 + br1 = {} * {} + {} = {}",
+        table1.nr, table2.br, table1.br, nested_loop_join_cost(table1, table2)
+    )
+}
+
+/*
+ * Block nested loop with the smaller relation as outer: the outer is read
+ * once in chunks of M - R blocks, R buffers being reserved for reading the
+ * inner and writing the output (one each by default), and the inner is
+ * scanned once per chunk, br_outer + ceil(br_outer / (M - R)) * br_inner.
+ * None when the reserved buffers leave no memory for the outer.
+ */
+pub fn block_nested_join_cost(table1: &impl TableStats, table2: &impl TableStats, memory_size: u32, reserved_buffers: u32) -> Option<u64> {
+    let (outer_br, inner_br) = block_nested_blocks(table1, table2);
+    let chunks = match block_nested_chunks(outer_br, memory_size, reserved_buffers) {
+        Some(x) => x,
+        None => {
+            debug!("block nested: {reserved_buffers} reserved buffers leave none of M = {memory_size} for the outer");
+            return None;
+        }
+    };
+    match block_nested_fits(table1, table2, memory_size, reserved_buffers) {
+        true => {
+            debug!("block nested: outer of {outer_br} blocks fits in M = {memory_size}, reading both once");
+            Some(outer_br as u64 + inner_br as u64)
+        }
+        false => {
+            debug!("block nested: outer of {outer_br} blocks in {chunks} chunks, each scanning the inner of {inner_br} blocks");
+            Some(outer_br as u64 + chunks * inner_br as u64)
+        }
+    }
+}
+
+/// Whether the smaller relation fits in the M - R blocks block nested loop
+/// holds the outer in, so both relations are read just once.
+pub fn block_nested_fits(table1: &impl TableStats, table2: &impl TableStats, memory_size: u32, reserved_buffers: u32) -> bool {
+    let (outer_br, _) = block_nested_blocks(table1, table2);
+    memory_size.checked_sub(reserved_buffers).is_some_and(|x| x > 0 && outer_br <= x)
+}
+
+/*
+ * Block nested loop when the outer relation is assumed not to fit, whatever
+ * the memory: only one of its blocks is held at a time, so the inner is
+ * scanned once per outer block, br_outer + br_outer * br_inner.
+ */
+pub fn block_nested_no_fit_cost(table1: &impl TableStats, table2: &impl TableStats) -> u64 {
+    let (outer_br, inner_br) = block_nested_blocks(table1, table2);
+    debug!("block nested: outer of {outer_br} blocks assumed not to fit, scanning the inner of {inner_br} blocks per block");
+    outer_br as u64 + outer_br as u64 * inner_br as u64
+}
+
+pub fn block_nested_no_fit_explain(table1: &Table, table2: &Table) -> String {
+    let (outer, inner) = block_nested_sides(table1, table2);
+    format!(
+        "Block Nested: br_outer + br_outer * br_inner = {} + {} * {} = {} (outer {} assumed not to fit)",
+        outer.br, outer.br, inner.br, block_nested_no_fit_cost(table1, table2), outer.name
+    )
+}
+
+/// Reserved buffers that leave block nested loop a single block for the
+/// outer relation, so it never fits and costs br_outer + br_outer * br_inner
+/// however large `memory_size` is.
+pub fn no_fit_reserved_buffers(memory_size: u32) -> u32 {
+    memory_size.saturating_sub(1)
+}
+
+/// The smaller relation is the outer one; the first table wins ties.
+fn block_nested_sides<'a>(table1: &'a Table, table2: &'a Table) -> (&'a Table, &'a Table) {
+    if table2.br < table1.br { (table2, table1) } else { (table1, table2) }
+}
+
+/// The block counts of the outer and inner relation, picked like `block_nested_sides`.
+fn block_nested_blocks(table1: &impl TableStats, table2: &impl TableStats) -> (u32, u32) {
+    if table2.br() < table1.br() { (table2.br(), table1.br()) } else { (table1.br(), table2.br()) }
+}
+
+/// Number of M - R block chunks the outer relation is read in.
+fn block_nested_chunks(outer_br: u32, memory_size: u32, reserved_buffers: u32) -> Option<u64> {
+    let chunk = memory_size.checked_sub(reserved_buffers).filter(|x| *x > 0)?;
+    Some(cmp::max(1, outer_br.div_ceil(chunk)) as u64)
+}
+
+pub fn block_nested_join_explain(table1: &Table, table2: &Table, memory_size: u32, reserved_buffers: u32) -> Option<String> {
+    let (outer, inner) = block_nested_sides(table1, table2);
+    let chunks = block_nested_chunks(outer.br, memory_size, reserved_buffers)?;
+    let cost = block_nested_join_cost(table1, table2, memory_size, reserved_buffers)?;
+    if block_nested_fits(table1, table2, memory_size, reserved_buffers) {
+        Some(format!("Block Nested: br1 + br2 = {} + {} = {cost} (fits in memory)", table1.br, table2.br))
+    } else {
+        Some(format!(
+            "Block Nested: br_outer + ceil(br_outer / (M - {reserved_buffers})) * br_inner = {} + {chunks} * {} = {cost} (outer {})",
+            outer.br, inner.br, outer.name
+        ))
+    }
+}
+
+/*
+ * Blocks fetched from `table` for one index lookup, matching
+ * nr * selectivity tuples. A clustering index stores them contiguously,
+ * ceil(matching_tuples / tuples_per_block) = ceil(br * selectivity), while
+ * a non-clustering index needs one I/O per matching tuple. A partial index
+ * only finds the matches among the fraction of rows it covers.
+ */
+pub fn index_fetch_cost(table: &impl TableStats, column: &Column, selectivity: f64) -> u64 {
+    let selectivity = selectivity * column.index_coverage.unwrap_or(1.0);
+    if column.clustered {
+        (table.br() as f64 * selectivity).ceil() as u64
+    } else {
+        (table.nr() as f64 * selectivity).ceil() as u64
+    }
+}
+
+/// Blocks read to find the matches a partial index on `column` leaves out:
+/// one scan of `table`, or none when the index covers every row.
+pub fn uncovered_rows_cost(table: &impl TableStats, column: &Column) -> u64 {
+    match column.index_coverage {
+        Some(x) if x < 1.0 => table.br() as u64,
+        _ => 0,
+    }
+}
+
+/// Blocks read to find a key in the index on `column`: the height of a
+/// B+-tree, or a constant `HASH_INDEX_LOOKUP_COST` for a hash index.
+pub fn index_lookup_cost(column: &Column, index_fanout: u32) -> u32 {
+    match column.index_type.unwrap_or_default() {
+        IndexType::BTree => height_of_index_tree(index_fanout, column.total_values),
+        IndexType::Hash => HASH_INDEX_LOOKUP_COST,
+    }
+}
+
+/*
+ * Indexed nested loop: every tuple of the outer relation probes the index
+ * on the other side, nr_outer * (lookup + fetch) + br_outer, taking the
+ * cheaper direction. A B+-tree lookup reads the height of the tree, a hash
+ * lookup a constant number of blocks. A composite index serves lookups on
+ * its first column. A partial index can't serve the whole join, so the
+ * rows it leaves out cost another scan of the inner relation.
+ */
+pub fn indexed_join_cost(table1: &impl TableStats, column1: &Column, table2: &impl TableStats, column2: &Column, index_fanout: u32) -> Option<u64> {
+    let selectivity = selectivity(column1, column2);
+    let mut cost: Option<u64> = None;
+    if table1.has_index_on(column1) {
+        let lookup_cost1: u64 = index_lookup_cost(column1, index_fanout) as u64 + index_fetch_cost(table1, column1, selectivity);
+        let total_cost1: u64 = table2.nr() as u64 * lookup_cost1 + table2.br() as u64 + uncovered_rows_cost(table1, column1);
+        debug!("indexed: probing {} for each of {} tuples at {lookup_cost1} blocks each costs {total_cost1}", column1.name, table2.nr());
+        cost = match cost {
+            None => Some(total_cost1),
+            Some(x) => Some(cmp::min(x, total_cost1))
+        }
+    }
+    if table2.has_index_on(column2) {
+        let lookup_cost2: u64 = index_lookup_cost(column2, index_fanout) as u64 + index_fetch_cost(table2, column2, selectivity);
+        let total_cost2: u64 = table1.nr() as u64 * lookup_cost2 + table1.br() as u64 + uncovered_rows_cost(table2, column2);
+        debug!("indexed: probing {} for each of {} tuples at {lookup_cost2} blocks each costs {total_cost2}", column2.name, table1.nr());
+        cost = match cost {
+            None => Some(total_cost2),
+            Some(x) => Some(cmp::min(x, total_cost2))
+        }
+    }
+    if cost.is_none() {
+        debug!("indexed: neither {} nor {} has an index", column1.name, column2.name);
+    }
+
+    cost
+}
+
+/// Spells out the cheaper side of `indexed_join_cost`: the outer relation is
+/// scanned and every tuple probes the index on the other side.
+pub fn indexed_join_explain(table1: &Table, column1: &Column, table2: &Table, column2: &Column, index_fanout: u32) -> Option<String> {
+    let selectivity = selectivity(column1, column2);
+    let mut best: Option<(u64, String)> = None;
+    for (outer, inner, column) in [(table2, table1, column1), (table1, table2, column2)] {
+        if !inner.has_index_on(column) {
+            continue;
+        }
+        let lookup = index_lookup_cost(column, index_fanout);
+        let label = match column.index_type.unwrap_or_default() {
+            IndexType::BTree => "height",
+            IndexType::Hash => "lookup",
+        };
+        let fetch = index_fetch_cost(inner, column, selectivity);
+        let uncovered = uncovered_rows_cost(inner, column);
+        let cost = outer.nr as u64 * (lookup as u64 + fetch) + outer.br as u64 + uncovered;
+        if best.as_ref().is_none_or(|(x, _)| cost < *x) {
+            let explanation = match uncovered {
+                0 => format!(
+                    "Indexed: nr * ({label} + fetch) + br = {} * ({lookup} + {fetch}) + {} = {cost} (probing {}.{})",
+                    outer.nr, outer.br, inner.name, column.name
+                ),
+                _ => format!(
+                    "Indexed: nr * ({label} + fetch) + br + br_inner = {} * ({lookup} + {fetch}) + {} + {uncovered} = {cost} \
+                     (probing {}.{}, whose partial index covers {}% of the rows)",
+                    outer.nr, outer.br, inner.name, column.name, coverage_percent(column)
+                ),
+            };
+            best = Some((cost, explanation));
+        }
+    }
+
+    best.map(|(_, explanation)| explanation)
+}
+
+/*
+ * Classic indexed nested loop with the outer and inner relation fixed:
+ * `outer` is scanned once and every one of its tuples probes the index on
+ * `inner_column`, br_outer + nr_outer * (lookup + matching_blocks). Unlike
+ * `indexed_join_cost` it never swaps the sides, and like it pays another
+ * scan of the inner relation when its index is a partial one. None without
+ * an index on the inner column.
+ */
+pub fn indexed_nested_loop_cost(outer: &impl TableStats, outer_column: &Column, inner: &impl TableStats, inner_column: &Column, index_fanout: u32) -> Option<u64> {
+    if !inner.has_index_on(inner_column) {
+        debug!("indexed nested loop: no index on inner column {}", inner_column.name);
+        return None;
+    }
+    let lookup = index_lookup_cost(inner_column, index_fanout) as u64;
+    let matching_blocks = index_fetch_cost(inner, inner_column, selectivity(outer_column, inner_column));
+    debug!("indexed nested loop: lookup {lookup} + {matching_blocks} matching blocks for each of {} outer tuples", outer.nr());
+    Some(outer.br() as u64 + outer.nr() as u64 * (lookup + matching_blocks) + uncovered_rows_cost(inner, inner_column))
+}
+
+pub fn indexed_nested_loop_explain(outer: &Table, outer_column: &Column, inner: &Table, inner_column: &Column, index_fanout: u32) -> Option<String> {
+    let cost = indexed_nested_loop_cost(outer, outer_column, inner, inner_column, index_fanout)?;
+    let lookup = index_lookup_cost(inner_column, index_fanout);
+    let matching_blocks = index_fetch_cost(inner, inner_column, selectivity(outer_column, inner_column));
+    let label = match inner_column.index_type.unwrap_or_default() {
+        IndexType::BTree => "height",
+        IndexType::Hash => "lookup",
+    };
+    Some(match uncovered_rows_cost(inner, inner_column) {
+        0 => format!(
+            "Indexed Nested Loop: br_outer + nr_outer * ({label} + matching_blocks) = {} + {} * ({lookup} + {matching_blocks}) = {cost} (outer {}, probing {}.{})",
+            outer.br, outer.nr, outer.name, inner.name, inner_column.name
+        ),
+        uncovered => format!(
+            "Indexed Nested Loop: br_outer + nr_outer * ({label} + matching_blocks) + br_inner = {} + {} * ({lookup} + {matching_blocks}) + {uncovered} = {cost} \
+             (outer {}, probing {}.{}, whose partial index covers {}% of the rows)",
+            outer.br, outer.nr, outer.name, inner.name, inner_column.name, coverage_percent(inner_column)
+        ),
+    })
+}
+
+/// The share of the rows the index on `column` covers, as a whole percentage.
+pub(crate) fn coverage_percent(column: &Column) -> u32 {
+    (column.index_coverage.unwrap_or(1.0) * 100.0).round() as u32
+}
+
+/// Block transfers of an external sort-merge of `br` blocks with `memory_size`
+/// blocks of memory: br * (2 * ceil(log_{M-1}(ceil(br / M))) + 1).
+///
+/// The initial pass writes ceil(br / M) sorted runs, and every merge pass
+/// combines M - 1 runs at a time, reading and writing all br blocks once.
+/// The final merge pass isn't written back. `memory_size` has to be at least 3.
+///
+/// ```
+/// use joiner::sorting_cost;
+///
+/// // A single run is sorted in memory and read once.
+/// assert_eq!(sorting_cost(100, 200), 100);
+/// // 12 runs merged in one pass with 13 blocks.
+/// assert_eq!(sorting_cost(150, 13), 450);
+/// // 100 runs need 3 passes merging 9 at a time.
+/// assert_eq!(sorting_cost(1000, 10), 7000);
+/// ```
+pub fn sorting_cost(br: u32, memory_size: u32) -> u64 {
+    let initial_runs = br.div_ceil(memory_size);
+    let merge_passes = (initial_runs as f64).log((memory_size - 1) as f64).ceil() as u64;
+    debug!("sort: {br} blocks in {initial_runs} runs of M = {memory_size}, {merge_passes} merge passes");
+    br as u64 * (2 * merge_passes + 1)
+}
+
+/// The smallest memory size M for which `sorting_cost` needs at most one
+/// merge pass, i.e. the ceil(br / M) initial runs can all be merged at once
+/// using M - 1 input buffers.
+pub fn min_memory_for_one_pass_sort(br: u32) -> u32 {
+    // One pass needs ceil(br / M) <= M - 1, which implies M > sqrt(br).
+    let mut memory_size = cmp::max(2, (br as f64).sqrt() as u32);
+    while br.div_ceil(memory_size) > memory_size - 1 {
+        memory_size += 1;
+    }
+
+    memory_size
+}
+
+/// Blocks spent sorting whichever inputs of a merge join aren't already
+/// sorted on their join column, scaled by the width of that column's key.
+pub fn merge_sort_cost(table1: &impl TableStats, column1: &Column, table2: &impl TableStats, column2: &Column, memory_size: u32) -> u64 {
+    let mut cost_to_sort: u64 = 0;
+    if !table1.is_sorted_on(column1) {
+        cost_to_sort += scale_by_key_width(sorting_cost(table1.br(), memory_size), key_width_factor(column1));
+    }
+    if !table2.is_sorted_on(column2) {
+        cost_to_sort += scale_by_key_width(sorting_cost(table2.br(), memory_size), key_width_factor(column2));
+    }
+
+    cost_to_sort
+}
+
+/*
+ * Sorting and hashing wide keys costs more than fixed-width integers. A
+ * char(n) or varchar(n) key counts n / 8 times an 8-byte integer key, at
+ * least once; numeric, unknown and missing types count once.
+ */
+pub fn key_width_factor(column: &Column) -> f64 {
+    let data_type = match &column.data_type {
+        Some(x) => x.trim().to_ascii_lowercase(),
+        None => return 1.0,
+    };
+    let width = data_type
+        .strip_prefix("varchar(")
+        .or_else(|| data_type.strip_prefix("char("))
+        .and_then(|x| x.strip_suffix(')'))
+        .and_then(|x| x.trim().parse::<u32>().ok());
+    match width {
+        Some(x) => (x as f64 / 8.0).max(1.0),
+        None => 1.0,
+    }
+}
+
+/// Scales `cost` by a key-width factor, rounding up to whole blocks.
+pub fn scale_by_key_width(cost: u64, factor: f64) -> u64 {
+    (cost as f64 * factor).ceil() as u64
+}
+
+/// Takes `discount` (from 0 up to 1) off blocks read sequentially, which
+/// prefetching and double-buffering make cheaper than random reads,
+/// rounding up to whole blocks.
+pub fn apply_sequential_discount(cost: u64, discount: f64) -> u64 {
+    (cost as f64 * (1.0 - discount)).ceil() as u64
+}
+
+/*
+ * Merge join: sort + br1 + br2, reading both sorted inputs once as long as
+ * the tuples sharing a join value on one side fit in the M - 2 blocks left
+ * beside an input and an output buffer. A value takes g = ceil(br / V)
+ * blocks, since nr / V tuples have it. When even the smaller group doesn't
+ * fit, it's joined chunk by chunk and the other side's group is re-read for
+ * every chunk after the first:
+ * min(V1, V2) * (ceil(g_small / (M - 2)) - 1) * g_large.
+ */
+pub fn merge_join_cost(table1: &impl TableStats, column1: &Column, table2: &impl TableStats, column2: &Column, memory_size: u32) -> u64 {
+    let sort = merge_sort_cost(table1, column1, table2, column2, memory_size);
+    let rescan = merge_rescan_cost(table1, column1, table2, column2, memory_size);
+    debug!("merge: sorting {sort} + br1 {} + br2 {} + re-reading {rescan}", table1.br(), table2.br());
+    sort + table1.br() as u64 + table2.br() as u64 + rescan
+}
+
+/// Blocks merge join re-reads because the tuples of one join value don't fit in memory.
+pub fn merge_rescan_cost(table1: &impl TableStats, column1: &Column, table2: &impl TableStats, column2: &Column, memory_size: u32) -> u64 {
+    if column1.total_values == 0 || column2.total_values == 0 {
+        return 0;
+    }
+    let group1 = table1.br().div_ceil(column1.total_values) as u64;
+    let group2 = table2.br().div_ceil(column2.total_values) as u64;
+    let (small, large) = (cmp::min(group1, group2), cmp::max(group1, group2));
+    let chunks = small.div_ceil(cmp::max(1, memory_size.saturating_sub(2)) as u64);
+    let values = cmp::min(column1.total_values, column2.total_values) as u64;
+
+    values * chunks.saturating_sub(1) * large
+}
+
+pub fn merge_join_explain(table1: &Table, column1: &Column, table2: &Table, column2: &Column, memory_size: u32) -> String {
+    let sort = merge_sort_cost(table1, column1, table2, column2, memory_size);
+    let cost = merge_join_cost(table1, column1, table2, column2, memory_size);
+    let sorted: Vec<&str> = [(table1, column1), (table2, column2)]
+        .iter()
+        .filter(|(table, column)| !table.is_sorted_on(column))
+        .map(|(table, _)| table.name.as_str())
+        .collect();
+    let note = match sorted.is_empty() {
+        true => String::from("both inputs already sorted"),
+        false => format!("sorting {}", sorted.join(" and ")),
+    };
+    match merge_rescan_cost(table1, column1, table2, column2, memory_size) {
+        0 => format!("Merge: sort + br1 + br2 = {sort} + {} + {} = {cost} ({note})", table1.br, table2.br),
+        rescan => format!(
+            "Merge: sort + br1 + br2 + rescan = {sort} + {} + {} + {rescan} = {cost} ({note}, value groups don't fit in memory)",
+            table1.br, table2.br
+        ),
+    }
+}
+
+/*
+ * Partitioned hash join: 3 * (br1 + br2) + nh when the build relation's
+ * partitions fit in memory (M^2 > br). Otherwise the partitions are
+ * recursively re-partitioned, each extra level reading and writing both
+ * relations once more: 2 * (br1 + br2) * ceil(log_{M-1}(br / M^2)).
+ * Recursion is only costed for the automatically picked build side; a
+ * forced build side whose partitions don't fit is infeasible.
+ */
+pub fn hash_join_cost(table1: &impl TableStats, table2: &impl TableStats, memory_size: u32, build: BuildSide) -> Option<u64> {
+    let (nh, extra_passes) = hash_partitioning(table1, table2, memory_size, build)?;
+    let total = table1.br() as u64 + table2.br() as u64;
+    Some(3 * total + nh + 2 * total * extra_passes)
+}
+
+/// Blocks of the relation a hash join builds from (the one `build` names, or
+/// the smaller one) and the memory it gets. The build relation dominates the
+/// cost, so its `memory_hint` takes precedence over `memory_size`.
+fn build_side(table1: &impl TableStats, table2: &impl TableStats, memory_size: u32, build: BuildSide) -> (u32, u32) {
+    let left = (table1.br(), table1.memory_hint());
+    let right = (table2.br(), table2.memory_hint());
+    let (build_br, hint) = match build {
+        BuildSide::Auto if table2.br() < table1.br() => right,
+        BuildSide::Auto | BuildSide::Left => left,
+        BuildSide::Right => right,
+    };
+    (build_br, hint.unwrap_or(memory_size))
+}
+
+/// The partition count nh and the number of recursive partitioning passes of a hash join.
+fn hash_partitioning(table1: &impl TableStats, table2: &impl TableStats, memory_size: u32, build: BuildSide) -> Option<(u64, u64)> {
+    let (build_br, memory_size) = build_side(table1, table2, memory_size, build);
+    let nh = (build_br / memory_size) as u64 + 1;
+    // Squared in u64 so memory sizes above u16::MAX don't wrap around.
+    let memory_squared = (memory_size as u64) * (memory_size as u64);
+    if memory_squared > build_br as u64 {
+        debug!("hash: build of {build_br} blocks in {nh} partitions of M = {memory_size}");
+        return Some((nh, 0));
+    }
+    if build != BuildSide::Auto {
+        debug!("hash: forced build of {build_br} blocks needs recursive partitioning with M = {memory_size}");
+        return None;
+    }
+
+    let extra_passes = (build_br as f64 / memory_squared as f64).log((memory_size - 1) as f64).ceil() as u64;
+    debug!("hash: build of {build_br} blocks needs {extra_passes} extra partitioning passes with M = {memory_size}");
+    Some((nh, extra_passes))
+}
+
+pub fn hash_join_explain(table1: &Table, table2: &Table, memory_size: u32, build: BuildSide) -> Option<String> {
+    let (nh, extra_passes) = hash_partitioning(table1, table2, memory_size, build)?;
+    let cost = hash_join_cost(table1, table2, memory_size, build)?;
+    let (br1, br2) = (table1.br, table2.br);
+    if extra_passes == 0 {
+        return Some(format!("Hash: 3 * (br1 + br2) + nh = 3 * ({br1} + {br2}) + {nh} = {cost}"));
+    }
+
+    Some(format!(
+        "Hash: 3 * (br1 + br2) + nh + 2 * (br1 + br2) * passes = 3 * ({br1} + {br2}) + {nh} + 2 * ({br1} + {br2}) * {extra_passes} = {cost} (recursive partitioning)"
+    ))
+}
+
+/// The smallest memory size M with M^2 > br, below which `hash_join_cost`
+/// has to partition a build relation of `smaller_br` blocks recursively.
+pub fn min_memory_for_hash(smaller_br: u32) -> u32 {
+    let mut memory_size = (smaller_br as f64).sqrt() as u64;
+    while memory_size * memory_size > smaller_br as u64 {
+        memory_size -= 1;
+    }
+    while memory_size * memory_size <= smaller_br as u64 {
+        memory_size += 1;
+    }
+
+    memory_size as u32
+}
+
+/*
+ * Hybrid hash join keeps the first of the nh = ceil(br / M) build partitions
+ * resident while partitioning, so neither relation writes out or re-reads
+ * that share: 3 * (br1 + br2) - 2 * (br1 + br2) / nh. A build relation that
+ * fits in memory outright is joined in a single scan of both inputs. Builds
+ * that would need recursive partitioning (M^2 <= br) aren't modeled.
+ */
+pub fn hybrid_hash_join_cost(table1: &impl TableStats, table2: &impl TableStats, memory_size: u32, build: BuildSide) -> Option<u64> {
+    let (build_br, memory_size) = build_side(table1, table2, memory_size, build);
+    let total = table1.br() as u64 + table2.br() as u64;
+    if build_br < memory_size {
+        debug!("hybrid hash: build of {build_br} blocks fits in M = {memory_size}");
+        return Some(total);
+    }
+    if (memory_size as u64) * (memory_size as u64) <= build_br as u64 {
+        debug!("hybrid hash: build of {build_br} blocks would need recursive partitioning with M = {memory_size}");
+        return None;
+    }
+
+    let nh = build_br.div_ceil(memory_size) as u64;
+    debug!("hybrid hash: build of {build_br} blocks in {nh} partitions, the first kept in memory");
+    Some(3 * total - 2 * total / nh)
+}
+
+pub fn hybrid_hash_join_explain(table1: &Table, table2: &Table, memory_size: u32, build: BuildSide) -> Option<String> {
+    let cost = hybrid_hash_join_cost(table1, table2, memory_size, build)?;
+    let (br1, br2) = (table1.br, table2.br);
+    let (build_br, memory_size) = build_side(table1, table2, memory_size, build);
+    if build_br < memory_size {
+        return Some(format!("Hybrid Hash: br1 + br2 = {br1} + {br2} = {cost} (build side fits in memory)"));
+    }
+
+    let nh = build_br.div_ceil(memory_size);
+    Some(format!(
+        "Hybrid Hash: 3 * (br1 + br2) - 2 * (br1 + br2) / nh = 3 * ({br1} + {br2}) - 2 * ({br1} + {br2}) / {nh} = {cost}"
+    ))
+}
+
+/*
+ * Blocks needed to write `rows` result tuples to disk. A result tuple is as
+ * wide as one tuple of each input, and an input tuple takes br / nr blocks,
+ * so the result takes ceil(rows * (br1 / nr1 + br2 / nr2)) blocks.
+ */
+pub fn output_blocks(table1: &impl TableStats, table2: &impl TableStats, rows: u64) -> u64 {
+    (rows as f64 * (blocks_per_row(table1) + blocks_per_row(table2))).ceil() as u64
+}
+
+fn blocks_per_row(table: &impl TableStats) -> f64 {
+    if table.nr() == 0 { 0.0 } else { table.br() as f64 / table.nr() as f64 }
+}
+
+/*
+ * False positive rate of a bloom filter over `keys` distinct keys that gets
+ * the M - 2 blocks of memory left after an input and an output buffer, with
+ * m = (M - 2) * BLOCK_SIZE * 8 bits and k = max(1, round(m / keys * ln 2))
+ * hash functions: (1 - e^(-k * keys / m))^k.
+ */
+pub fn bloom_filter_false_positive_rate(memory_size: u32, keys: u32) -> f64 {
+    if keys == 0 {
+        return 0.0;
+    }
+    let bits = memory_size.saturating_sub(2) as f64 * BLOCK_SIZE as f64 * 8.0;
+    if bits == 0.0 {
+        return 1.0;
+    }
+    let hashes = (bits / keys as f64 * std::f64::consts::LN_2).round().max(1.0);
+    (1.0 - (-hashes * keys as f64 / bits).exp()).powf(hashes)
+}
+
+/*
+ * Semi-join keeping the rows of `probe` that have a match in `build`. A bloom
+ * filter over the build keys is filled in one scan of the build relation,
+ * then the probe relation is scanned once and only the rows passing the
+ * filter are hash joined with the build relation:
+ * br_build + br_probe + hash_join_cost(build, passing probe rows).
+ * Assuming the smaller key set is contained in the larger one, a fraction
+ * f = min(1, V(build) / V(probe)) of the probe rows has a match, and the
+ * filter passes f + (1 - f) * false_positive_rate of them.
+ */
+pub fn semi_join_cost(build: &impl TableStats, build_column: &Column, probe: &impl TableStats, probe_column: &Column, memory_size: u32) -> Option<u64> {
+    let matching = matching_fraction(build_column, probe_column);
+    let false_positives = bloom_filter_false_positive_rate(memory_size, build_column.total_values);
+    let pass_rate = matching + (1.0 - matching) * false_positives;
+    debug!("semi-join: a fraction {matching:.4} of the probe rows match and {pass_rate:.4} pass the filter");
+    let passing = Filtered {
+        table: probe,
+        nr: (probe.nr() as f64 * pass_rate).ceil() as u32,
+        br: (probe.br() as f64 * pass_rate).ceil() as u32,
+    };
+
+    hash_join_cost(build, &passing, memory_size, BuildSide::Auto).map(|cost| build.br() as u64 + probe.br() as u64 + cost)
+}
+
+/// The rows of `table` a filter passes: `nr` of its rows in `br` blocks,
+/// with everything else as `table` has it.
+struct Filtered<'a, T> {
+    table: &'a T,
+    nr: u32,
+    br: u32,
+}
+
+impl<T: TableStats> TableStats for Filtered<'_, T> {
+    fn nr(&self) -> u32 {
+        self.nr
+    }
+
+    fn br(&self) -> u32 {
+        self.br
+    }
+
+    fn columns(&self) -> &[Column] {
+        self.table.columns()
+    }
+
+    fn sorted_column(&self) -> Option<&Column> {
+        self.table.sorted_column()
+    }
+
+    fn has_index_on(&self, column: &Column) -> bool {
+        self.table.has_index_on(column)
+    }
+
+    fn is_sorted_on(&self, column: &Column) -> bool {
+        self.table.is_sorted_on(column)
+    }
+
+    fn is_intermediate(&self) -> bool {
+        self.table.is_intermediate()
+    }
+
+    fn memory_hint(&self) -> Option<u32> {
+        self.table.memory_hint()
+    }
+}
+
+/// Fraction of the probe rows with a match in the build relation,
+/// min(1, V(build) / V(probe)), assuming the smaller key set is contained in
+/// the larger one.
+fn matching_fraction(build_column: &Column, probe_column: &Column) -> f64 {
+    match probe_column.total_values {
+        0 => 0.0,
+        x => (build_column.total_values as f64 / x as f64).min(1.0),
+    }
+}
+
+/*
+ * Anti-join keeping the rows of `probe` without a match in `build`, for NOT
+ * IN and NOT EXISTS. It hashes the way `semi_join_cost` does: the rows the
+ * bloom filter rejects have no match and are kept right away, and the rows
+ * passing it are hash joined with the build relation to tell its false
+ * positives, which are kept as well, from the real matches. It reads the
+ * same blocks and only keeps the other rows; see `anti_join_cardinality`.
+ */
+pub fn anti_join_cost(build: &impl TableStats, build_column: &Column, probe: &impl TableStats, probe_column: &Column, memory_size: u32) -> Option<u64> {
+    semi_join_cost(build, build_column, probe, probe_column, memory_size)
+}
+
+/// Rows of `probe` an anti-join with `build` keeps: the probe rows minus the
+/// nr_probe * min(1, V(build) / V(probe)) that have a match.
+pub fn anti_join_cardinality(build_column: &Column, probe: &impl TableStats, probe_column: &Column) -> u64 {
+    let matched = (probe.nr() as f64 * matching_fraction(build_column, probe_column)).ceil() as u64;
+    (probe.nr() as u64).saturating_sub(matched)
+}
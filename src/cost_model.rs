@@ -0,0 +1,39 @@
+use std::{fmt, str::FromStr};
+
+use crate::JoinerError;
+
+/// Blocks of I/O one tuple comparison is worth unless `--cpu-weight` says otherwise.
+pub const DEFAULT_CPU_WEIGHT: f64 = 0.001;
+
+/// What the cost of a join method counts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum CostModel {
+    /// Block transfers only.
+    #[default]
+    Io,
+    /// Block transfers plus the tuple comparisons, weighted into blocks; see
+    /// `add_cpu_costs`.
+    IoCpu,
+}
+
+impl FromStr for CostModel {
+    type Err = JoinerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "io" => Ok(CostModel::Io),
+            "io+cpu" => Ok(CostModel::IoCpu),
+            _ => Err(JoinerError::Input(format!("unknown cost model '{s}', expected one of: io, io+cpu"))),
+        }
+    }
+}
+
+impl fmt::Display for CostModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            CostModel::Io => "io",
+            CostModel::IoCpu => "io+cpu",
+        };
+        f.pad(name)
+    }
+}
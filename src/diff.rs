@@ -0,0 +1,94 @@
+//! What changed between two versions of the metadata, such as statistics
+//! exported on different days.
+
+use std::fmt;
+
+use crate::Table;
+
+/// One difference found by `diff_metadata`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetadataChange {
+    TableAdded(String),
+    TableRemoved(String),
+    ColumnAdded { table: String, column: String },
+    ColumnRemoved { table: String, column: String },
+    RowsChanged { table: String, from: u32, to: u32 },
+    BlocksChanged { table: String, from: u32, to: u32 },
+    DistinctValuesChanged { table: String, column: String, from: u32, to: u32 },
+    IndexedChanged { table: String, column: String, from: bool, to: bool },
+}
+
+/// One line of a changelog: `+` for added, `-` for removed and `~` for changed.
+impl fmt::Display for MetadataChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MetadataChange::TableAdded(table) => write!(f, "+ table {table}"),
+            MetadataChange::TableRemoved(table) => write!(f, "- table {table}"),
+            MetadataChange::ColumnAdded { table, column } => write!(f, "+ column {table}.{column}"),
+            MetadataChange::ColumnRemoved { table, column } => write!(f, "- column {table}.{column}"),
+            MetadataChange::RowsChanged { table, from, to } => write!(f, "~ {table} nr: {from} -> {to}"),
+            MetadataChange::BlocksChanged { table, from, to } => write!(f, "~ {table} br: {from} -> {to}"),
+            MetadataChange::DistinctValuesChanged { table, column, from, to } => {
+                write!(f, "~ {table}.{column} total_values: {from} -> {to}")
+            }
+            MetadataChange::IndexedChanged { table, column, from, to } => {
+                write!(f, "~ {table}.{column} indexed: {from} -> {to}")
+            }
+        }
+    }
+}
+
+/// Every change from `old` to `new`: tables in the order `old` lists them,
+/// followed by the tables only `new` has. Tables and columns are matched by name.
+pub fn diff_metadata(old: &[Table], new: &[Table]) -> Vec<MetadataChange> {
+    let mut changes: Vec<MetadataChange> = Vec::new();
+    for before in old {
+        let after = match new.iter().find(|x| x.name == before.name) {
+            Some(x) => x,
+            None => {
+                changes.push(MetadataChange::TableRemoved(before.name.clone()));
+                continue;
+            }
+        };
+        let table = || before.name.clone();
+        if before.nr != after.nr {
+            changes.push(MetadataChange::RowsChanged { table: table(), from: before.nr, to: after.nr });
+        }
+        if before.br != after.br {
+            changes.push(MetadataChange::BlocksChanged { table: table(), from: before.br, to: after.br });
+        }
+        for column in &before.columns {
+            let changed = match after.columns.iter().find(|x| x.name == column.name) {
+                Some(x) => x,
+                None => {
+                    changes.push(MetadataChange::ColumnRemoved { table: table(), column: column.name.clone() });
+                    continue;
+                }
+            };
+            if column.total_values != changed.total_values {
+                changes.push(MetadataChange::DistinctValuesChanged {
+                    table: table(),
+                    column: column.name.clone(),
+                    from: column.total_values,
+                    to: changed.total_values,
+                });
+            }
+            if column.indexed != changed.indexed {
+                changes.push(MetadataChange::IndexedChanged {
+                    table: table(),
+                    column: column.name.clone(),
+                    from: column.indexed,
+                    to: changed.indexed,
+                });
+            }
+        }
+        for column in after.columns.iter().filter(|x| !before.columns.iter().any(|y| y.name == x.name)) {
+            changes.push(MetadataChange::ColumnAdded { table: table(), column: column.name.clone() });
+        }
+    }
+    for table in new.iter().filter(|x| !old.iter().any(|y| y.name == x.name)) {
+        changes.push(MetadataChange::TableAdded(table.name.clone()));
+    }
+
+    changes
+}
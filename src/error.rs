@@ -0,0 +1,82 @@
+use std::{error, fmt, io, path::{Path, PathBuf}};
+
+#[derive(Debug)]
+pub enum JoinerError {
+    /// An I/O failure, with the file it happened on if there was one.
+    IO { path: Option<PathBuf>, source: io::Error },
+    /// Malformed JSON, with the file it was read from if there was one and
+    /// the table entry at fault, such as `the table at index 2 (Orders)`.
+    Parse { path: Option<PathBuf>, source: serde_json::Error, table: Option<String> },
+    Input(String),
+    /// A malformed predicate, with the byte range `start..end` of the input at fault.
+    Syntax { message: String, span: (usize, usize) },
+    Usage(String),
+    TableNotFound(String),
+    /// A column missing from `table`, with the names of the columns it does have.
+    ColumnNotFound { table: String, column: String, available: Vec<String> },
+}
+
+impl JoinerError {
+    /// Attaches `path` to an I/O or parse error that doesn't name its file yet.
+    pub fn with_path<P: AsRef<Path>>(self, path: P) -> Self {
+        match self {
+            JoinerError::IO { path: None, source } => JoinerError::IO { path: Some(path.as_ref().to_path_buf()), source },
+            JoinerError::Parse { path: None, source, table } => {
+                JoinerError::Parse { path: Some(path.as_ref().to_path_buf()), source, table }
+            }
+            err => err,
+        }
+    }
+}
+
+impl fmt::Display for JoinerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JoinerError::IO { path: Some(path), source } => write!(f, "IO error in {}: {source}", path.display()),
+            JoinerError::IO { path: None, source } => write!(f, "IO error: {source}"),
+            JoinerError::Parse { path, source, table } => {
+                // serde_json appends the location to its message; report it up front instead.
+                let message = source.to_string();
+                let location = format!(" at line {} column {}", source.line(), source.column());
+                let message = message.strip_suffix(&location).unwrap_or(&message);
+                // Errors from an already parsed value, such as YAML metadata, have no location.
+                let table = table.as_ref().map(|x| format!(", in {x}")).unwrap_or_default();
+                match (path, source.line()) {
+                    (Some(path), 0) => write!(f, "parse error in {}{table}: {message}", path.display()),
+                    (None, 0) => write!(f, "parse error{table}: {message}"),
+                    (Some(path), _) => write!(f, "parse error in {} at line {}, column {}{table}: {message}", path.display(), source.line(), source.column()),
+                    (None, _) => write!(f, "parse error at line {}, column {}{table}: {message}", source.line(), source.column()),
+                }
+            }
+            JoinerError::Input(msg) => write!(f, "invalid input: {msg}"),
+            JoinerError::Syntax { message, .. } => write!(f, "invalid input: {message}"),
+            JoinerError::Usage(msg) => write!(f, "{msg}"),
+            JoinerError::TableNotFound(table) => write!(f, "table not found with name {table}"),
+            JoinerError::ColumnNotFound { table, column, available } => {
+                write!(f, "column {column} not found in table {table} (available: {})", available.join(", "))
+            }
+        }
+    }
+}
+
+impl error::Error for JoinerError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            JoinerError::IO { source, .. } => Some(source),
+            JoinerError::Parse { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for JoinerError {
+    fn from(err: io::Error) -> Self {
+        JoinerError::IO { path: None, source: err }
+    }
+}
+
+impl From<serde_json::Error> for JoinerError {
+    fn from(err: serde_json::Error) -> Self {
+        JoinerError::Parse { path: None, source: err, table: None }
+    }
+}
@@ -0,0 +1,638 @@
+use serde::{Serialize, Serializer};
+use std::{cmp, collections::HashMap, fmt, str::FromStr};
+
+use crate::{
+    apply_sequential_discount, block_nested_join_cost, block_nested_join_explain,
+    block_nested_no_fit_cost, block_nested_no_fit_explain, estimate_filtered_join_cardinality,
+    hash_join_cost, hash_join_explain, hybrid_hash_join_cost, hybrid_hash_join_explain,
+    indexed_join_cost, indexed_join_explain, indexed_nested_loop_cost, indexed_nested_loop_explain,
+    key_width_factor, merge_join_cost, merge_join_explain, merge_rescan_cost, merge_sort_cost,
+    nested_loop_join_cost, nested_loop_join_explain, scale_by_key_width, uncovered_rows_cost,
+    BuildSide, Column, Comparison, JoinType, JoinerError, ResolvedJoin, Table, MIN_MEMORY_BLOCKS,
+    RANGE_SELECTIVITY,
+};
+use crate::cost::coverage_percent;
+
+/// The join algorithms the cost model knows about.
+///
+/// Methods are ordered by tie-break priority: when two methods cost the same,
+/// the one declared first wins, i.e. Block Nested < Nested Loop < Indexed <
+/// Indexed Nested Loop < Merge < Hash < Hybrid Hash. The simpler methods come first since they
+/// make fewer assumptions about memory and the data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum JoinMethod {
+    BlockNested,
+    NestedLoop,
+    Indexed,
+    /// Indexed nested loop with the left table as the outer relation; see
+    /// `indexed_nested_loop_cost`. `Indexed` may probe either side.
+    IndexedNestedLoop,
+    Merge,
+    Hash,
+    HybridHash,
+}
+
+impl JoinMethod {
+    /// Every method, in tie-break priority order.
+    pub const ALL: [JoinMethod; 7] = [
+        JoinMethod::BlockNested,
+        JoinMethod::NestedLoop,
+        JoinMethod::Indexed,
+        JoinMethod::IndexedNestedLoop,
+        JoinMethod::Merge,
+        JoinMethod::Hash,
+        JoinMethod::HybridHash,
+    ];
+
+    /// Fewest memory blocks the method runs in, below which it doesn't apply:
+    ///
+    /// - Block Nested: one block of the outer relation besides the
+    ///   `reserved_buffers` for the inner relation and the output, and at
+    ///   least `MIN_MEMORY_BLOCKS`.
+    /// - Nested Loop, Indexed and Indexed Nested Loop: `MIN_MEMORY_BLOCKS`,
+    ///   a block of each relation or its index and one for the output.
+    /// - Merge: `MIN_MEMORY_BLOCKS`, since an external sort merges M - 1 runs
+    ///   at a time and needs at least two.
+    /// - Hash and Hybrid Hash: `MIN_MEMORY_BLOCKS`, one input block and at
+    ///   least two partitions.
+    pub fn min_memory(self, reserved_buffers: u32) -> u32 {
+        match self {
+            JoinMethod::BlockNested => cmp::max(MIN_MEMORY_BLOCKS, reserved_buffers.saturating_add(1)),
+            JoinMethod::NestedLoop
+            | JoinMethod::Indexed
+            | JoinMethod::IndexedNestedLoop
+            | JoinMethod::Merge
+            | JoinMethod::Hash
+            | JoinMethod::HybridHash => MIN_MEMORY_BLOCKS,
+        }
+    }
+}
+
+impl FromStr for JoinMethod {
+    type Err = JoinerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "block" | "block-nested" => Ok(JoinMethod::BlockNested),
+            "nested" | "nested-loop" => Ok(JoinMethod::NestedLoop),
+            "indexed" => Ok(JoinMethod::Indexed),
+            "indexed-nested" | "indexed-nested-loop" => Ok(JoinMethod::IndexedNestedLoop),
+            "merge" => Ok(JoinMethod::Merge),
+            "hash" => Ok(JoinMethod::Hash),
+            "hybrid" | "hybrid-hash" => Ok(JoinMethod::HybridHash),
+            _ => Err(JoinerError::Input(format!(
+                "unknown join method '{s}', expected one of: block, nested, indexed, indexed-nested, merge, hash, hybrid"
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for JoinMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            JoinMethod::BlockNested => "Block Nested Join",
+            JoinMethod::NestedLoop => "Nested Loop Join",
+            JoinMethod::Indexed => "Indexed Join",
+            JoinMethod::IndexedNestedLoop => "Indexed Nested Loop Join",
+            JoinMethod::Merge => "Merge Join",
+            JoinMethod::Hash => "Hash Join",
+            JoinMethod::HybridHash => "Hybrid Hash Join",
+        };
+        f.pad(name)
+    }
+}
+
+/// Serialized as its human-readable name, the way reports print it.
+impl Serialize for JoinMethod {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// The cheapest way to perform a join, as picked by `estimate_best_join`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JoinEstimate {
+    pub method: JoinMethod,
+    pub cost_blocks: u64,
+    pub estimated_rows: u64,
+    /// Blocks spent sorting the inputs, when the winner is a merge join that has to sort.
+    pub sort_cost: Option<u64>,
+}
+
+/// Computes the cost of every join method for `join`, `None` marking a
+/// method that isn't applicable.
+pub fn join_candidates(join: &ResolvedJoin, memory_size: u32, index_fanout: u32, build: BuildSide, reserved_buffers: u32) -> Vec<(JoinMethod, Option<u64>)> {
+    JoinMethod::ALL
+        .iter()
+        .map(|method| (*method, candidate_cost(join, *method, memory_size, index_fanout, build, reserved_buffers)))
+        .collect()
+}
+
+/// The cost of joining with `method` alone, or an error saying why it can't be used.
+pub fn method_cost(join: &ResolvedJoin, method: JoinMethod, memory_size: u32, index_fanout: u32, build: BuildSide, reserved_buffers: u32) -> Result<u64, JoinerError> {
+    match candidate_cost(join, method, memory_size, index_fanout, build, reserved_buffers) {
+        Some(cost) => Ok(cost),
+        None => Err(JoinerError::Input(format!(
+            "method not applicable: {method} can't be used {}", unavailable_reason(join, method, memory_size)
+        ))),
+    }
+}
+
+/// Picks the cheapest applicable candidate like `cheapest_candidate`, but
+/// when none is applicable, the error says why each one wasn't.
+pub fn choose_candidate(join: &ResolvedJoin, candidates: &[(JoinMethod, Option<u64>)], memory_size: u32) -> Result<(JoinMethod, u64), JoinerError> {
+    if let Some(best) = cheapest_candidate(candidates) {
+        return Ok(best);
+    }
+    let reasons: Vec<String> = candidates
+        .iter()
+        .map(|(method, _)| format!("{method} can't be used {}", unavailable_reason(join, *method, memory_size)))
+        .collect();
+
+    Err(JoinerError::Input(format!("no applicable join method: {}", reasons.join("; "))))
+}
+
+/*
+ * Assumptions behind `method`'s cost that the statistics of `join` strain.
+ * Hash joins assume keys spread evenly over the partitions, which fails when
+ * the rows of a single build key, about br / V blocks, already overflow the
+ * M - 2 blocks a partition is built in. Merge join assumes the tuples of a
+ * join value fit in memory; when they don't, the re-reads it's charged for
+ * are only a rough estimate. A partial index can't serve the whole join, so
+ * index lookups through one depend on another scan of the table.
+ */
+pub fn method_caveats(join: &ResolvedJoin, method: JoinMethod, memory_size: u32, build: BuildSide) -> Vec<String> {
+    let ResolvedJoin { table1, table2, keys, .. } = join;
+    let partition_blocks = memory_size.saturating_sub(2);
+    let mut caveats: Vec<String> = Vec::new();
+    match method {
+        JoinMethod::Hash | JoinMethod::HybridHash => {
+            let build_left = match build {
+                BuildSide::Auto => table1.br <= table2.br,
+                BuildSide::Left => true,
+                BuildSide::Right => false,
+            };
+            for (column1, column2) in keys {
+                let (table, column) = if build_left { (table1, column1) } else { (table2, column2) };
+                let group_blocks = table.br.div_ceil(cmp::max(1, column.total_values));
+                if group_blocks > partition_blocks {
+                    caveats.push(format!(
+                        "{method} assumes an even spread of keys, but {}.{} has only {} distinct values for {} rows, \
+                         so each takes about {group_blocks} blocks, more than the {partition_blocks} blocks a partition is built in",
+                        table.name, column.name, column.total_values, table.nr
+                    ));
+                }
+            }
+        }
+        JoinMethod::Merge => {
+            if let Some((column1, column2)) = merge_key(join) {
+                if merge_rescan_cost(table1, column1, table2, column2, memory_size) > 0 {
+                    caveats.push(format!(
+                        "{method} assumes the tuples of one {}.{} value fit in memory, but they don't, \
+                         so its cost for re-reading them is only approximate",
+                        table1.name, column1.name
+                    ));
+                }
+            }
+        }
+        JoinMethod::Indexed | JoinMethod::IndexedNestedLoop => {
+            let probed = keys.iter().flat_map(|(column1, column2)| [(table1, column1), (table2, column2)]);
+            for (table, column) in probed.filter(|(table, column)| table.has_index_on(column) && uncovered_rows_cost(*table, column) > 0) {
+                if method == JoinMethod::IndexedNestedLoop && table.name != table2.name {
+                    continue;
+                }
+                caveats.push(format!(
+                    "the partial index on {}.{} only covers {}% of the rows, so {method} also scans {} for the matches it leaves out",
+                    table.name, column.name, coverage_percent(column), table.name
+                ));
+            }
+        }
+        JoinMethod::BlockNested | JoinMethod::NestedLoop => {}
+    }
+    if table1.block_bytes() != table2.block_bytes() {
+        caveats.push(format!(
+            "{} uses {}-byte blocks but {} uses {}-byte blocks, so {method} adds block counts of different sizes",
+            table1.name, table1.block_bytes(), table2.name, table2.block_bytes()
+        ));
+    }
+
+    caveats
+}
+
+fn unavailable_reason(join: &ResolvedJoin, method: JoinMethod, memory_size: u32) -> String {
+    if memory_size < MIN_MEMORY_BLOCKS {
+        return format!("with {memory_size} blocks of memory, it needs at least {MIN_MEMORY_BLOCKS}");
+    }
+    let reason = match (method, join.keys.is_empty()) {
+        (JoinMethod::BlockNested, _) => "when its reserved buffers take up all the memory",
+        (JoinMethod::NestedLoop, _) => "here, it wasn't considered",
+        (JoinMethod::Indexed, false) => "without an index on a column compared for equality",
+        (JoinMethod::IndexedNestedLoop, false) => "without an index on a column of the right table compared for equality",
+        (JoinMethod::Merge, _) => "without an equality or range condition",
+        (JoinMethod::Hash | JoinMethod::HybridHash, false) => "when the build relation needs recursive partitioning",
+        _ => "without an equality condition",
+    };
+
+    reason.to_string()
+}
+
+/*
+ * A sharded table stores nr and br per shard. The join runs on every shard of
+ * the larger relation, with the smaller relation broadcast whole to each of
+ * them, so it costs shards times joining one shard with all of the smaller
+ * relation. Shipping the broadcast copies isn't counted beyond the reads the
+ * per-shard joins already make.
+ */
+fn candidate_cost(join: &ResolvedJoin, method: JoinMethod, memory_size: u32, index_fanout: u32, build: BuildSide, reserved_buffers: u32) -> Option<u64> {
+    match per_shard_join(join) {
+        Some((shard1, shard2, shards)) => {
+            let per_shard = ResolvedJoin {
+                table1: &shard1,
+                table2: &shard2,
+                keys: join.keys.clone(),
+                filters: join.filters.clone(),
+                wrapped: join.wrapped.clone(),
+            };
+            shard_cost(&per_shard, method, memory_size, index_fanout, build, reserved_buffers).map(|cost| cost * shards as u64)
+        }
+        None => shard_cost(join, method, memory_size, index_fanout, build, reserved_buffers),
+    }
+}
+
+/// The two sides of the join one shard runs and the number of shards, if either table is sharded.
+fn per_shard_join(join: &ResolvedJoin) -> Option<(Table, Table, u32)> {
+    let (table1, table2) = (join.table1, join.table2);
+    if table1.shards <= 1 && table2.shards <= 1 {
+        return None;
+    }
+    let total_blocks = |table: &Table| table.br as u64 * table.shards as u64;
+    let one_shard = |table: &Table| Table { shards: 1, ..table.clone() };
+    let whole = |table: &Table| Table {
+        nr: table.total_rows().min(u32::MAX as u64) as u32,
+        br: total_blocks(table).min(u32::MAX as u64) as u32,
+        shards: 1,
+        ..table.clone()
+    };
+
+    match total_blocks(table1) >= total_blocks(table2) {
+        true => Some((one_shard(table1), whole(table2), table1.shards)),
+        false => Some((whole(table1), one_shard(table2), table2.shards)),
+    }
+}
+
+fn shard_cost(join: &ResolvedJoin, method: JoinMethod, memory_size: u32, index_fanout: u32, build: BuildSide, reserved_buffers: u32) -> Option<u64> {
+    let ResolvedJoin { table1, table2, keys, .. } = join;
+    if memory_size < method.min_memory(reserved_buffers) {
+        return None;
+    }
+    // Hashing and index lookups only bring equal keys together.
+    if keys.is_empty() && matches!(method, JoinMethod::Indexed | JoinMethod::IndexedNestedLoop | JoinMethod::Hash | JoinMethod::HybridHash) {
+        return None;
+    }
+
+    match method {
+        JoinMethod::BlockNested => block_nested_join_cost(table1, table2, memory_size, reserved_buffers),
+        JoinMethod::NestedLoop => Some(nested_loop_join_cost(table1, table2)),
+        // Any single indexed column can drive the lookup; the remaining conditions are filters.
+        JoinMethod::Indexed => index_keys(join)
+            .iter()
+            .filter_map(|(column1, column2)| indexed_join_cost(table1, column1, table2, column2, index_fanout))
+            .min(),
+        JoinMethod::IndexedNestedLoop => index_keys(join)
+            .iter()
+            .filter_map(|(column1, column2)| indexed_nested_loop_cost(table1, column1, table2, column2, index_fanout))
+            .min(),
+        JoinMethod::Merge => merge_key(join).map(|(column1, column2)| {
+            let (sorted1, sorted2) = merge_tables(join, column1, column2);
+            merge_join_cost(&sorted1, column1, &sorted2, column2, memory_size)
+        }),
+        JoinMethod::Hash => hash_join_cost(table1, table2, memory_size, build)
+            .map(|cost| scale_by_key_width(cost, hash_key_factor(join))),
+        JoinMethod::HybridHash => hybrid_hash_join_cost(table1, table2, memory_size, build)
+            .map(|cost| scale_by_key_width(cost, hash_key_factor(join))),
+    }
+}
+
+/*
+ * The join keys as index lookups see them. A column wrapped in a function
+ * is looked up by the function's result, which no index on the column
+ * itself stores, so its copy here has no index.
+ */
+fn index_keys(join: &ResolvedJoin) -> Vec<(Column, Column)> {
+    let lookup_column = |column: &Column, wrapped: &[&Column]| match wrapped.iter().any(|x| x.name == column.name) {
+        true => Column { indexed: false, index_group: None, ..column.clone() },
+        false => column.clone(),
+    };
+    join.keys
+        .iter()
+        .map(|(column1, column2)| (lookup_column(column1, &join.wrapped.0), lookup_column(column2, &join.wrapped.1)))
+        .collect()
+}
+
+/*
+ * The tables as merge join sees them. A column wrapped in a function is
+ * ordered by the function's result, not by the column itself, so a table
+ * sorted on it has to be sorted again and its copy here has no sort order.
+ */
+fn merge_tables(join: &ResolvedJoin, column1: &Column, column2: &Column) -> (Table, Table) {
+    let merged_table = |table: &Table, column: &Column, wrapped: &[&Column]| match wrapped.iter().any(|x| x.name == column.name) {
+        true => Table { sorted_column: None, ..table.clone() },
+        false => table.clone(),
+    };
+    (merged_table(join.table1, column1, &join.wrapped.0), merged_table(join.table2, column2, &join.wrapped.1))
+}
+
+/// Hashing a composite key costs as much as hashing its widest column.
+fn hash_key_factor(join: &ResolvedJoin) -> f64 {
+    join.keys
+        .iter()
+        .flat_map(|(column1, column2)| [key_width_factor(column1), key_width_factor(column2)])
+        .fold(1.0, f64::max)
+}
+
+/// Like `join_candidates`, but describes the formula behind every applicable cost.
+pub fn explain_candidates(join: &ResolvedJoin, memory_size: u32, index_fanout: u32, build: BuildSide, reserved_buffers: u32) -> Vec<(JoinMethod, Option<String>)> {
+    let (shard1, shard2, shards) = match per_shard_join(join) {
+        Some(x) => x,
+        None => return explain_shard(join, memory_size, index_fanout, build, reserved_buffers),
+    };
+    let per_shard = ResolvedJoin {
+        table1: &shard1,
+        table2: &shard2,
+        keys: join.keys.clone(),
+        filters: join.filters.clone(),
+        wrapped: join.wrapped.clone(),
+    };
+    explain_shard(&per_shard, memory_size, index_fanout, build, reserved_buffers)
+        .into_iter()
+        .map(|(method, explanation)| {
+            let total = candidate_cost(join, method, memory_size, index_fanout, build, reserved_buffers).unwrap_or_default();
+            (method, explanation.map(|x| format!("{x}, per shard * {shards} shards = {total}")))
+        })
+        .collect()
+}
+
+fn explain_shard(join: &ResolvedJoin, memory_size: u32, index_fanout: u32, build: BuildSide, reserved_buffers: u32) -> Vec<(JoinMethod, Option<String>)> {
+    let ResolvedJoin { table1, table2, keys, .. } = join;
+    if memory_size < MIN_MEMORY_BLOCKS {
+        return JoinMethod::ALL.iter().map(|method| (*method, None)).collect();
+    }
+
+    let index_keys = index_keys(join);
+    let indexed_key = index_keys
+        .iter()
+        .filter_map(|(column1, column2)| {
+            indexed_join_cost(table1, column1, table2, column2, index_fanout).map(|cost| (cost, column1, column2))
+        })
+        .min_by_key(|(cost, _, _)| *cost);
+    let indexed = indexed_key
+        .and_then(|(_, column1, column2)| indexed_join_explain(table1, column1, table2, column2, index_fanout));
+    let indexed_nested_loop = index_keys
+        .iter()
+        .filter_map(|(column1, column2)| {
+            indexed_nested_loop_cost(table1, column1, table2, column2, index_fanout).map(|cost| (cost, column1, column2))
+        })
+        .min_by_key(|(cost, _, _)| *cost)
+        .and_then(|(_, column1, column2)| indexed_nested_loop_explain(table1, column1, table2, column2, index_fanout));
+    let (hash, hybrid_hash) = match keys.is_empty() {
+        true => (None, None),
+        false => (
+            hash_join_explain(table1, table2, memory_size, build),
+            hybrid_hash_join_explain(table1, table2, memory_size, build),
+        ),
+    };
+    let factor = hash_key_factor(join);
+    let widened = |explanation: Option<String>, method: JoinMethod| match factor > 1.0 {
+        true => explanation.map(|x| format!(
+            "{x}, scaled by {factor} for the key width to {}",
+            shard_cost(join, method, memory_size, index_fanout, build, reserved_buffers).unwrap_or_default()
+        )),
+        false => explanation,
+    };
+    let (hash, hybrid_hash) = (widened(hash, JoinMethod::Hash), widened(hybrid_hash, JoinMethod::HybridHash));
+    let merge = merge_key(join).map(|(column1, column2)| {
+        let (sorted1, sorted2) = merge_tables(join, column1, column2);
+        merge_join_explain(&sorted1, column1, &sorted2, column2, memory_size)
+    });
+
+    vec![
+        (JoinMethod::BlockNested, block_nested_join_explain(table1, table2, memory_size, reserved_buffers)),
+        (JoinMethod::NestedLoop, Some(nested_loop_join_explain(table1, table2))),
+        (JoinMethod::Indexed, indexed),
+        (JoinMethod::IndexedNestedLoop, indexed_nested_loop),
+        (JoinMethod::Merge, merge),
+        (JoinMethod::Hash, hash),
+        (JoinMethod::HybridHash, hybrid_hash),
+    ]
+}
+
+/*
+ * The columns a merge join sorts on. A composite key is sorted as the
+ * concatenation of its columns, so merge join can reuse an existing sort
+ * order on the leading column only. Without an equality, a range comparison
+ * can still be merged; `!=` can't.
+ */
+fn merge_key<'a>(join: &ResolvedJoin<'a>) -> Option<(&'a Column, &'a Column)> {
+    if let Some(key) = join.keys.first() {
+        return Some(*key);
+    }
+    join.filters
+        .iter()
+        .find(|(_, op, _)| *op != Comparison::Ne)
+        .map(|(column1, _, column2)| (*column1, *column2))
+}
+
+/// Picks the cheapest applicable candidate, breaking ties by the priority
+/// order of `JoinMethod` whatever order the candidates come in.
+pub fn cheapest_candidate(candidates: &[(JoinMethod, Option<u64>)]) -> Option<(JoinMethod, u64)> {
+    candidates
+        .iter()
+        .filter_map(|(method, cost)| cost.map(|x| (*method, x)))
+        .min_by_key(|(method, cost)| (*cost, *method))
+}
+
+/// The `k` cheapest applicable candidates, cheapest first. Ties are ranked
+/// by the priority order of `JoinMethod`, like `cheapest_candidate` picks.
+pub fn top_candidates(candidates: &[(JoinMethod, Option<u64>)], k: usize) -> Vec<(JoinMethod, u64)> {
+    let mut ranked: Vec<(JoinMethod, u64)> = candidates
+        .iter()
+        .filter_map(|(method, cost)| cost.map(|x| (*method, x)))
+        .collect();
+    ranked.sort_by_key(|(method, cost)| (*cost, *method));
+    ranked.truncate(k);
+
+    ranked
+}
+
+/*
+ * Block nested loop and merge join only ever scan their inputs (and merge
+ * join's sort runs) front to back, so their whole cost gets the sequential
+ * discount. Indexed joins fetch blocks in random order and hash joins write
+ * and read scattered partitions, so they're left as they are.
+ */
+pub fn discount_sequential_scans(candidates: &mut [(JoinMethod, Option<u64>)], discount: f64) {
+    for (method, cost) in candidates.iter_mut() {
+        if matches!(method, JoinMethod::BlockNested | JoinMethod::Merge) {
+            *cost = cost.map(|x| apply_sequential_discount(x, discount));
+        }
+    }
+}
+
+/*
+ * Tuple comparisons each method makes, for the io+cpu cost model. Nested
+ * loops compare every pair of tuples, nr1 * nr2. Merge and hash joins touch
+ * every tuple about once while sorting, merging, partitioning and probing,
+ * nr1 + nr2, and so do indexed joins, which scan one side and only compare
+ * the tuples the index fetches. Rows are counted across all shards.
+ */
+pub fn join_comparisons(join: &ResolvedJoin, method: JoinMethod) -> f64 {
+    let (nr1, nr2) = (join.table1.total_rows() as f64, join.table2.total_rows() as f64);
+    match method {
+        JoinMethod::BlockNested | JoinMethod::NestedLoop => nr1 * nr2,
+        JoinMethod::Indexed | JoinMethod::IndexedNestedLoop | JoinMethod::Merge | JoinMethod::Hash | JoinMethod::HybridHash => nr1 + nr2,
+    }
+}
+
+/// Adds `cpu_weight` blocks per comparison `join_comparisons` counts to
+/// every applicable candidate, rounding up to whole blocks.
+pub fn add_cpu_costs(candidates: &mut [(JoinMethod, Option<u64>)], join: &ResolvedJoin, cpu_weight: f64) {
+    for (method, cost) in candidates.iter_mut() {
+        let cpu = (join_comparisons(join, *method) * cpu_weight).ceil() as u64;
+        *cost = cost.map(|x| x.saturating_add(cpu));
+    }
+}
+
+/// Replaces the cost of block nested loop, where it's applicable, with the
+/// one `block_nested_no_fit_cost` gives, paid once per shard like
+/// `join_candidates` does for sharded tables.
+pub fn assume_block_nested_no_fit(candidates: &mut [(JoinMethod, Option<u64>)], join: &ResolvedJoin) {
+    let no_fit = match per_shard_join(join) {
+        Some((shard1, shard2, shards)) => block_nested_no_fit_cost(&shard1, &shard2) * shards as u64,
+        None => block_nested_no_fit_cost(join.table1, join.table2),
+    };
+    for (_, cost) in candidates.iter_mut().filter(|(method, _)| *method == JoinMethod::BlockNested) {
+        *cost = cost.map(|_| no_fit);
+    }
+}
+
+/// Describes the cost `assume_block_nested_no_fit` gives block nested loop.
+pub fn explain_block_nested_no_fit(join: &ResolvedJoin) -> String {
+    match per_shard_join(join) {
+        Some((shard1, shard2, shards)) => format!(
+            "{}, per shard * {shards} shards = {}",
+            block_nested_no_fit_explain(&shard1, &shard2),
+            block_nested_no_fit_cost(&shard1, &shard2) * shards as u64
+        ),
+        None => block_nested_no_fit_explain(join.table1, join.table2),
+    }
+}
+
+/// How many times the cost of `baseline` a method's `cost` is, or `None`
+/// when the baseline costs nothing and there's nothing to compare with.
+pub fn cost_ratio(cost: u64, baseline: u64) -> Option<f64> {
+    match baseline {
+        0 => None,
+        x => Some(cost as f64 / x as f64),
+    }
+}
+
+/// Formats `candidates` as CSV with one row per method; methods that aren't
+/// applicable get an empty cost and `available` set to false.
+pub fn candidates_to_csv(candidates: &[(JoinMethod, Option<u64>)], estimated_rows: u64) -> String {
+    let mut csv = String::from("method,cost_blocks,estimated_rows,available\n");
+    for (method, cost) in candidates {
+        let cost = match cost {
+            Some(x) => x.to_string(),
+            None => String::new(),
+        };
+        csv += &format!("{method},{cost},{estimated_rows},{}\n", !cost.is_empty());
+    }
+
+    csv
+}
+
+/// The cheapest method and its cost at each of `memory_sizes`, or `None`
+/// where no method applies, to show where the best method changes.
+pub fn sweep_memory(join: &ResolvedJoin, memory_sizes: &[u32], index_fanout: u32, build: BuildSide, reserved_buffers: u32) -> Vec<(u32, Option<(JoinMethod, u64)>)> {
+    memory_sizes
+        .iter()
+        .map(|memory_size| {
+            let candidates = join_candidates(join, *memory_size, index_fanout, build, reserved_buffers);
+            (*memory_size, cheapest_candidate(&candidates))
+        })
+        .collect()
+}
+
+/// Runs every candidate method for `join` and describes the cheapest one.
+pub fn estimate_best_join(join: &ResolvedJoin, join_type: JoinType, memory_size: u32, index_fanout: u32, build: BuildSide, reserved_buffers: u32) -> Result<JoinEstimate, JoinerError> {
+    let candidates = join_candidates(join, memory_size, index_fanout, build, reserved_buffers);
+    let (method, cost_blocks) = choose_candidate(join, &candidates, memory_size)?;
+    let sort_cost = match (method, merge_key(join)) {
+        (JoinMethod::Merge, Some((column1, column2))) => {
+            Some(merge_sort_cost(join.table1, column1, join.table2, column2, memory_size)).filter(|x| *x > 0)
+        }
+        _ => None,
+    };
+
+    Ok(JoinEstimate {
+        method,
+        cost_blocks,
+        estimated_rows: estimate_filtered_join_cardinality(join.table1, join.table2, &join.keys, &join.filters, RANGE_SELECTIVITY, join_type),
+        sort_cost,
+    })
+}
+
+/// What an estimate depends on besides the tables' statistics, which don't
+/// change within a run.
+#[derive(PartialEq, Eq, Hash)]
+struct EstimateKey {
+    tables: (String, String),
+    keys: Vec<(String, String)>,
+    filters: Vec<(String, Comparison, String)>,
+    join_type: JoinType,
+    memory_size: u32,
+    index_fanout: u32,
+    build: BuildSide,
+    reserved_buffers: u32,
+}
+
+/// Remembers the `estimate_best_join` result of every join seen in a run,
+/// so repeated predicates aren't costed again.
+#[derive(Default)]
+pub struct EstimateCache {
+    estimates: HashMap<EstimateKey, JoinEstimate>,
+    computed: usize,
+}
+
+impl EstimateCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Like `estimate_best_join`, reusing the result for a join estimated before.
+    pub fn estimate(&mut self, join: &ResolvedJoin, join_type: JoinType, memory_size: u32, index_fanout: u32, build: BuildSide, reserved_buffers: u32) -> Result<JoinEstimate, JoinerError> {
+        let key = EstimateKey {
+            tables: (join.table1.name.clone(), join.table2.name.clone()),
+            keys: join.keys.iter().map(|(x, y)| (x.name.clone(), y.name.clone())).collect(),
+            filters: join.filters.iter().map(|(x, op, y)| (x.name.clone(), *op, y.name.clone())).collect(),
+            join_type,
+            memory_size,
+            index_fanout,
+            build,
+            reserved_buffers,
+        };
+        if let Some(estimate) = self.estimates.get(&key) {
+            return Ok(estimate.clone());
+        }
+
+        let estimate = estimate_best_join(join, join_type, memory_size, index_fanout, build, reserved_buffers)?;
+        self.computed += 1;
+        self.estimates.insert(key, estimate.clone());
+        Ok(estimate)
+    }
+
+    /// How many estimates were actually computed rather than reused.
+    pub fn computed(&self) -> usize {
+        self.computed
+    }
+}
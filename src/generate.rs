@@ -0,0 +1,78 @@
+//! Random but consistent metadata for stress-testing the cost functions.
+
+use serde_json::{json, Value};
+
+use crate::{derive_block_count, BLOCK_SIZE};
+
+/// Bounds on the schema `generate_metadata` makes up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GenerateOptions {
+    pub tables: u32,
+    /// Most columns a table gets; every table has at least its `id` column.
+    pub max_columns: u32,
+    /// Most rows a table gets; every table has at least one.
+    pub max_rows: u32,
+}
+
+impl Default for GenerateOptions {
+    fn default() -> Self {
+        GenerateOptions { tables: 10, max_columns: 8, max_rows: 1_000_000 }
+    }
+}
+
+/// A splitmix64 generator: good enough for made-up statistics and easy to reseed.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut x = self.0;
+        x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        x ^ (x >> 31)
+    }
+
+    /// A number in `low..=high`.
+    fn range(&mut self, low: u32, high: u32) -> u32 {
+        low + (self.next() % ((high - low) as u64 + 1)) as u32
+    }
+
+    fn chance(&mut self, percent: u32) -> bool {
+        self.range(1, 100) <= percent
+    }
+}
+
+/// Makes up metadata for `options.tables` tables named `T1`, `T2`, ... that
+/// passes `validate_tables`: every table has a unique `id` column it's sorted
+/// on, and no column has more distinct values than the table has rows.
+/// The same `seed` always gives the same metadata.
+pub fn generate_metadata(options: &GenerateOptions, seed: u64) -> Value {
+    let mut rng = Rng(seed);
+    let tables: Vec<Value> = (1..=options.tables)
+        .map(|i| {
+            let nr = rng.range(1, options.max_rows.max(1));
+            let tuple_size = rng.range(16, 512);
+            let br = derive_block_count(nr, tuple_size, BLOCK_SIZE);
+            let id = json!({ "name": "id", "indexed": true, "total_values": nr });
+            let mut columns = vec![id.clone()];
+            for j in 1..rng.range(1, options.max_columns.max(1)) {
+                columns.push(json!({
+                    "name": format!("c{j}"),
+                    "indexed": rng.chance(30),
+                    "total_values": rng.range(1, nr),
+                }));
+            }
+
+            json!({
+                "name": format!("T{i}"),
+                "nr": nr,
+                "br": br,
+                "tupleSize": tuple_size,
+                "columns": columns,
+                "sortedColumn": id,
+            })
+        })
+        .collect();
+
+    Value::Array(tables)
+}
@@ -0,0 +1,286 @@
+//! A small gzip (RFC 1952) reader with its own DEFLATE (RFC 1951) decoder,
+//! enough to load compressed metadata without pulling in a compression crate.
+
+use std::io;
+
+const MAGIC: [u8; 2] = [0x1f, 0x8b];
+const METHOD_DEFLATE: u8 = 8;
+
+const FLAG_HEADER_CRC: u8 = 0x02;
+const FLAG_EXTRA: u8 = 0x04;
+const FLAG_NAME: u8 = 0x08;
+const FLAG_COMMENT: u8 = 0x10;
+
+const MAX_CODE_LENGTH: usize = 15;
+/// Order the code length code lengths of a dynamic block are stored in.
+const CODE_LENGTH_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+/// Base match length and extra bits of the length symbols 257 to 285.
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+/// Base distance and extra bits of the distance symbols 0 to 29.
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097,
+    6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13];
+
+/// Whether `data` starts with the gzip magic number.
+pub fn is_gzip(data: &[u8]) -> bool {
+    data.starts_with(&MAGIC)
+}
+
+/// Decompresses a single-member gzip file, checking its CRC-32 and length.
+pub fn decompress(data: &[u8]) -> io::Result<Vec<u8>> {
+    if !is_gzip(data) || data.len() < 18 {
+        return Err(corrupt("not a gzip file"));
+    }
+    if data[2] != METHOD_DEFLATE {
+        return Err(corrupt("unsupported compression method"));
+    }
+    let flags = data[3];
+    let mut pos = 10;
+    if flags & FLAG_EXTRA != 0 {
+        let len = u16::from_le_bytes([byte_at(data, pos)?, byte_at(data, pos + 1)?]) as usize;
+        pos += 2 + len;
+    }
+    for flag in [FLAG_NAME, FLAG_COMMENT] {
+        if flags & flag != 0 {
+            // Zero-terminated strings.
+            while byte_at(data, pos)? != 0 {
+                pos += 1;
+            }
+            pos += 1;
+        }
+    }
+    if flags & FLAG_HEADER_CRC != 0 {
+        pos += 2;
+    }
+    if pos + 8 > data.len() {
+        return Err(corrupt("truncated header"));
+    }
+
+    let mut reader = BitReader { data: &data[..data.len() - 8], pos: pos * 8 };
+    let output = inflate(&mut reader)?;
+    let trailer = &data[data.len() - 8..];
+    let crc = u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+    let size = u32::from_le_bytes([trailer[4], trailer[5], trailer[6], trailer[7]]);
+    if crc32(&output) != crc || output.len() as u32 != size {
+        return Err(corrupt("checksum mismatch"));
+    }
+
+    Ok(output)
+}
+
+fn corrupt(reason: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("corrupt gzip data: {reason}"))
+}
+
+fn byte_at(data: &[u8], pos: usize) -> io::Result<u8> {
+    data.get(pos).copied().ok_or_else(|| corrupt("truncated header"))
+}
+
+/// Reads a DEFLATE stream least significant bit first.
+struct BitReader<'a> {
+    data: &'a [u8],
+    /// Position in bits.
+    pos: usize,
+}
+
+impl BitReader<'_> {
+    fn bit(&mut self) -> io::Result<u32> {
+        let byte = match self.data.get(self.pos / 8) {
+            Some(x) => *x,
+            None => return Err(corrupt("unexpected end of data")),
+        };
+        let bit = (byte >> (self.pos % 8)) & 1;
+        self.pos += 1;
+        Ok(bit as u32)
+    }
+
+    fn bits(&mut self, count: u8) -> io::Result<u32> {
+        let mut value = 0;
+        for i in 0..count {
+            value |= self.bit()? << i;
+        }
+        Ok(value)
+    }
+
+    fn align_to_byte(&mut self) {
+        self.pos = self.pos.div_ceil(8) * 8;
+    }
+}
+
+/// A canonical Huffman code, as the number of codes of every length and the
+/// symbols ordered by code.
+struct Huffman {
+    counts: [u16; MAX_CODE_LENGTH + 1],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Self {
+        let mut counts = [0u16; MAX_CODE_LENGTH + 1];
+        for length in lengths {
+            counts[*length as usize] += 1;
+        }
+        counts[0] = 0;
+        let mut symbols: Vec<u16> = Vec::new();
+        for length in 1..=MAX_CODE_LENGTH {
+            for (symbol, x) in lengths.iter().enumerate() {
+                if *x as usize == length {
+                    symbols.push(symbol as u16);
+                }
+            }
+        }
+
+        Huffman { counts, symbols }
+    }
+
+    fn decode(&self, reader: &mut BitReader) -> io::Result<u16> {
+        // Codes of one length are consecutive, starting at `first`.
+        let (mut code, mut first, mut index) = (0u32, 0u32, 0usize);
+        for length in 1..=MAX_CODE_LENGTH {
+            code |= reader.bit()?;
+            let count = self.counts[length] as u32;
+            if code < first + count {
+                return Ok(self.symbols[index + (code - first) as usize]);
+            }
+            index += count as usize;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+
+        Err(corrupt("invalid Huffman code"))
+    }
+}
+
+fn inflate(reader: &mut BitReader) -> io::Result<Vec<u8>> {
+    let mut output: Vec<u8> = Vec::new();
+    loop {
+        let last = reader.bit()? == 1;
+        match reader.bits(2)? {
+            0 => stored_block(reader, &mut output)?,
+            1 => {
+                let (literals, distances) = fixed_codes();
+                compressed_block(reader, &mut output, &literals, &distances)?;
+            }
+            2 => {
+                let (literals, distances) = dynamic_codes(reader)?;
+                compressed_block(reader, &mut output, &literals, &distances)?;
+            }
+            _ => return Err(corrupt("invalid block type")),
+        }
+        if last {
+            return Ok(output);
+        }
+    }
+}
+
+fn stored_block(reader: &mut BitReader, output: &mut Vec<u8>) -> io::Result<()> {
+    reader.align_to_byte();
+    let len = reader.bits(16)?;
+    let complement = reader.bits(16)?;
+    if len != !complement & 0xffff {
+        return Err(corrupt("stored block length mismatch"));
+    }
+    let start = reader.pos / 8;
+    let bytes = match reader.data.get(start..start + len as usize) {
+        Some(x) => x,
+        None => return Err(corrupt("unexpected end of data")),
+    };
+    output.extend_from_slice(bytes);
+    reader.pos += len as usize * 8;
+    Ok(())
+}
+
+fn fixed_codes() -> (Huffman, Huffman) {
+    let mut lengths = [0u8; 288];
+    lengths[..144].fill(8);
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    lengths[280..].fill(8);
+
+    (Huffman::new(&lengths), Huffman::new(&[5; 30]))
+}
+
+fn dynamic_codes(reader: &mut BitReader) -> io::Result<(Huffman, Huffman)> {
+    let literal_count = reader.bits(5)? as usize + 257;
+    let distance_count = reader.bits(5)? as usize + 1;
+    let code_length_count = reader.bits(4)? as usize + 4;
+
+    let mut code_lengths = [0u8; 19];
+    for i in CODE_LENGTH_ORDER.iter().take(code_length_count) {
+        code_lengths[*i] = reader.bits(3)? as u8;
+    }
+    let code_length_code = Huffman::new(&code_lengths);
+
+    let mut lengths: Vec<u8> = Vec::new();
+    while lengths.len() < literal_count + distance_count {
+        let (length, repeat) = match code_length_code.decode(reader)? {
+            x @ 0..=15 => (x as u8, 1),
+            16 => match lengths.last() {
+                Some(previous) => (*previous, 3 + reader.bits(2)?),
+                None => return Err(corrupt("repeated code length without a previous one")),
+            },
+            17 => (0, 3 + reader.bits(3)?),
+            _ => (0, 11 + reader.bits(7)?),
+        };
+        lengths.extend(std::iter::repeat_n(length, repeat as usize));
+    }
+    if lengths.len() > literal_count + distance_count {
+        return Err(corrupt("too many code lengths"));
+    }
+
+    let (literals, distances) = lengths.split_at(literal_count);
+    Ok((Huffman::new(literals), Huffman::new(distances)))
+}
+
+fn compressed_block(reader: &mut BitReader, output: &mut Vec<u8>, literals: &Huffman, distances: &Huffman) -> io::Result<()> {
+    loop {
+        let symbol = literals.decode(reader)? as usize;
+        if symbol < 256 {
+            output.push(symbol as u8);
+            continue;
+        }
+        if symbol == 256 {
+            return Ok(());
+        }
+
+        let i = symbol - 257;
+        if i >= LENGTH_BASE.len() {
+            return Err(corrupt("invalid length symbol"));
+        }
+        let length = LENGTH_BASE[i] as usize + reader.bits(LENGTH_EXTRA[i])? as usize;
+        let i = distances.decode(reader)? as usize;
+        if i >= DISTANCE_BASE.len() {
+            return Err(corrupt("invalid distance symbol"));
+        }
+        let distance = DISTANCE_BASE[i] as usize + reader.bits(DISTANCE_EXTRA[i])? as usize;
+        if distance > output.len() {
+            return Err(corrupt("distance before the start of the data"));
+        }
+        // Copied a byte at a time, since a match may overlap what it produces.
+        let start = output.len() - distance;
+        for j in 0..length {
+            output.push(output[start + j]);
+        }
+    }
+}
+
+/// CRC-32 as used by gzip (reflected polynomial 0xedb88320).
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = match crc & 1 {
+                1 => (crc >> 1) ^ 0xedb8_8320,
+                _ => crc >> 1,
+            };
+        }
+    }
+
+    !crc
+}
@@ -0,0 +1,446 @@
+use std::{fmt, fs, io, path::Path, str::FromStr};
+
+use crate::{JoinerError, MIN_MEMORY_BLOCKS};
+
+/// A `(table, column)` pair naming one side of a join predicate.
+pub type ColumnRef = (String, String);
+
+/// The comparison a join condition applies between its two columns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Comparison {
+    /// The same comparison with its operands swapped, e.g. `a < b` is `b > a`.
+    pub fn flipped(self) -> Self {
+        match self {
+            Comparison::Eq => Comparison::Eq,
+            Comparison::Ne => Comparison::Ne,
+            Comparison::Lt => Comparison::Gt,
+            Comparison::Le => Comparison::Ge,
+            Comparison::Gt => Comparison::Lt,
+            Comparison::Ge => Comparison::Le,
+        }
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            Comparison::Eq => "=",
+            Comparison::Ne => "!=",
+            Comparison::Lt => "<",
+            Comparison::Le => "<=",
+            Comparison::Gt => ">",
+            Comparison::Ge => ">=",
+        };
+        f.pad(symbol)
+    }
+}
+
+/// A single condition `<table1>.<column1> <op> <table2>.<column2>`, where
+/// either side may be wrapped in a function such as `UPPER(<table>.<column>)`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JoinCondition {
+    pub left: ColumnRef,
+    pub op: Comparison,
+    pub right: ColumnRef,
+    /// The function wrapping the left column, if any.
+    pub left_function: Option<String>,
+    /// The function wrapping the right column, if any.
+    pub right_function: Option<String>,
+}
+
+impl JoinCondition {
+    /// The same condition with its sides swapped.
+    pub fn flipped(self) -> Self {
+        JoinCondition {
+            left: self.right,
+            op: self.op.flipped(),
+            right: self.left,
+            left_function: self.right_function,
+            right_function: self.left_function,
+        }
+    }
+}
+
+impl fmt::Display for JoinCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let side = |(table, column): &ColumnRef, function: &Option<String>| match function {
+            Some(x) => format!("{x}({table}.{column})"),
+            None => format!("{table}.{column}"),
+        };
+        write!(f, "{} {} {}", side(&self.left, &self.left_function), self.op, side(&self.right, &self.right_function))
+    }
+}
+
+/// A parsed join predicate between two tables, displayed in canonical form
+/// such as `Orders.cust_id = Customers.id AND Orders.region = Customers.region`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Predicate {
+    pub conditions: Vec<JoinCondition>,
+}
+
+impl FromStr for Predicate {
+    type Err = JoinerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Predicate { conditions: parse_predicate(s)? })
+    }
+}
+
+impl fmt::Display for Predicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let conditions: Vec<String> = self.conditions.iter().map(JoinCondition::to_string).collect();
+        f.pad(&conditions.join(" AND "))
+    }
+}
+
+/// A byte range `start..end` of the predicate being parsed.
+type Span = (usize, usize);
+
+const INPUT_FORMAT: &str = "<table1>.<column1> = <table2>.<column2>";
+
+/*
+ * The input format: <table1>.<column1> <op> <table2>.<column2> [AND ...]
+ * For example,
+ * Orders.cust_id = Customers.id
+ * Orders.cust_id = Customers.id AND Orders.region = Customers.region
+ * Orders.placed_at >= Promotions.starts_at
+ */
+pub fn read_user_input() -> Result<Vec<JoinCondition>, JoinerError> {
+    let mut buffer = String::new();
+    io::stdin().read_line(&mut buffer)?;
+
+    parse_predicate(&buffer)
+}
+
+/// Like `read_user_input`, but the conditions may relate any number of tables.
+pub fn read_join_graph() -> Result<Vec<JoinCondition>, JoinerError> {
+    let mut buffer = String::new();
+    io::stdin().read_line(&mut buffer)?;
+
+    parse_conditions(&buffer)
+}
+
+/// Checks that a run which may not block on stdin was given its predicate as
+/// an argument, so `read_user_input` is never reached. `--repl` always reads
+/// from stdin.
+pub fn check_non_interactive(join: Option<&str>, repl: bool) -> Result<(), JoinerError> {
+    if repl {
+        return Err(JoinerError::Usage(String::from(
+            "--no-interactive can't be combined with --repl, which reads predicates from stdin"
+        )));
+    }
+    match join {
+        Some(_) => Ok(()),
+        None => Err(JoinerError::Usage(String::from(
+            "--no-interactive needs the join predicate to be given with --join"
+        ))),
+    }
+}
+
+/// Parses an `AND`-separated list of join conditions between two tables.
+///
+/// Every condition is oriented like the first one, so `A.x = B.x AND B.y < A.y`
+/// yields `[A.x = B.x, A.y > B.y]`.
+pub fn parse_predicate(input: &str) -> Result<Vec<JoinCondition>, JoinerError> {
+    let mut conditions = spanned_conditions(input)?;
+
+    let (table1, table2) = (conditions[0].0.left.0.clone(), conditions[0].0.right.0.clone());
+    for (condition, span) in conditions.iter_mut().skip(1) {
+        let (left_table, right_table) = (&condition.left.0, &condition.right.0);
+        if *left_table == table2 && *right_table == table1 && table1 != table2 {
+            *condition = condition.clone().flipped();
+        } else if *left_table != table1 || *right_table != table2 {
+            return Err(JoinerError::Syntax {
+                message: format!("every condition must join {table1} with {table2}, found {left_table} and {right_table}"),
+                span: *span,
+            });
+        }
+    }
+
+    Ok(conditions.into_iter().map(|(condition, _)| condition).collect())
+}
+
+/// A predicate from a batch file and the line it's on, counting from 1.
+pub type NumberedPredicate = (usize, Result<Vec<JoinCondition>, JoinerError>);
+
+/// Parses every line of `input` as a predicate like `parse_predicate`,
+/// skipping blank lines and `#` comments. A line that doesn't parse keeps
+/// its error so the rest of the batch can still be evaluated.
+pub fn parse_predicates(input: &str) -> Vec<NumberedPredicate> {
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .map(|(i, line)| (i + 1, parse_predicate(line)))
+        .collect()
+}
+
+/// Reads a batch file of predicates, one per line, with `parse_predicates`.
+pub fn read_predicates_file<P: AsRef<Path>>(path: P) -> Result<Vec<NumberedPredicate>, JoinerError> {
+    match fs::read_to_string(&path) {
+        Ok(content) => Ok(parse_predicates(&content)),
+        Err(err) => Err(JoinerError::from(err).with_path(path)),
+    }
+}
+
+/// Parses an `AND`-separated list of join conditions between any tables.
+pub fn parse_conditions(input: &str) -> Result<Vec<JoinCondition>, JoinerError> {
+    Ok(spanned_conditions(input)?.into_iter().map(|(condition, _)| condition).collect())
+}
+
+/// Like `parse_conditions`, keeping the byte range of `input` every condition came from.
+fn spanned_conditions(input: &str) -> Result<Vec<(JoinCondition, Span)>, JoinerError> {
+    let mut conditions: Vec<(JoinCondition, Span)> = Vec::new();
+    // Byte range of the tokens seen since the last AND, and of that AND.
+    let mut condition: Option<Span> = None;
+    let mut last_and: Option<Span> = None;
+    // An empty token marks the end of the input, closing the last condition like an AND.
+    for (start, token) in tokens(input).into_iter().chain([(input.len(), "")]) {
+        let end = start + token.len();
+        if !token.is_empty() && !token.eq_ignore_ascii_case("and") {
+            condition = Some((condition.map_or(start, |(x, _)| x), end));
+            continue;
+        }
+        let (begin, finish) = match condition {
+            Some(x) => x,
+            None => return Err(JoinerError::Syntax {
+                message: format!("empty condition around AND in '{}'", input.trim()),
+                span: if token.is_empty() { last_and.unwrap_or((start, end)) } else { (start, end) },
+            }),
+        };
+        conditions.push((parse_condition_at(&input[begin..finish], begin)?, (begin, finish)));
+        condition = None;
+        last_and = Some((start, end));
+    }
+
+    Ok(conditions)
+}
+
+/// The whitespace-separated tokens of `input` with the byte offset each starts at.
+fn tokens(input: &str) -> Vec<(usize, &str)> {
+    let mut tokens: Vec<(usize, &str)> = Vec::new();
+    let mut start: Option<usize> = None;
+    for (i, c) in input.char_indices().chain([(input.len(), ' ')]) {
+        match (start, c.is_whitespace()) {
+            (None, false) => start = Some(i),
+            (Some(x), true) => {
+                tokens.push((x, &input[x..i]));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+
+    tokens
+}
+
+/// The byte range of `text` without its surrounding whitespace, where `text` starts at `offset`.
+fn trimmed_span(text: &str, offset: usize) -> Span {
+    let start = offset + text.len() - text.trim_start().len();
+    (start, start + text.trim().len())
+}
+
+/// Parses a single join condition such as `Orders.cust_id = Customers.id`,
+/// where the comparison is one of `=`, `!=`, `<>`, `<`, `<=`, `>` or `>=`.
+pub fn parse_join_condition(input: &str) -> Result<JoinCondition, JoinerError> {
+    parse_condition_at(input, 0)
+}
+
+/// Like `parse_join_condition`, for a condition starting `offset` bytes into the predicate.
+fn parse_condition_at(input: &str, offset: usize) -> Result<JoinCondition, JoinerError> {
+    let start = match input.find(['=', '!', '<', '>']) {
+        Some(x) => x,
+        None => return Err(JoinerError::Syntax {
+            message: format!("missing '=' in '{}', expected {INPUT_FORMAT}", input.trim()),
+            span: trimmed_span(input, offset),
+        }),
+    };
+    let (op, len) = match &input[start..] {
+        x if x.starts_with("<=") => (Comparison::Le, 2),
+        x if x.starts_with(">=") => (Comparison::Ge, 2),
+        x if x.starts_with("!=") || x.starts_with("<>") => (Comparison::Ne, 2),
+        x if x.starts_with('=') => (Comparison::Eq, 1),
+        x if x.starts_with('<') => (Comparison::Lt, 1),
+        x if x.starts_with('>') => (Comparison::Gt, 1),
+        _ => return Err(JoinerError::Syntax {
+            message: format!("unknown comparison operator in '{}', expected one of =, !=, <, <=, >, >=", input.trim()),
+            span: (offset + start, offset + start + 1),
+        }),
+    };
+    let (left, right) = (&input[..start], &input[start + len..]);
+    let (left, left_function) = parse_side(left, "left", offset)?;
+    let (right, right_function) = parse_side(right, "right", offset + start + len)?;
+
+    Ok(JoinCondition { left, op, right, left_function, right_function })
+}
+
+/// Parses one side of a condition, a column or a function applied to one
+/// such as `UPPER(Orders.code)`, into the column and the function's name.
+fn parse_side(side: &str, which: &str, offset: usize) -> Result<(ColumnRef, Option<String>), JoinerError> {
+    let trimmed = side.trim();
+    let (function, argument) = match trimmed.strip_suffix(')').and_then(|x| x.split_once('(')) {
+        Some(x) => x,
+        None => return Ok((parse_column_ref(side, which, offset)?, None)),
+    };
+    let function = function.trim();
+    if function.is_empty() || !function.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(JoinerError::Syntax {
+            message: format!("invalid function name in {which} side '{trimmed}', expected FUNC(<table>.<column>)"),
+            span: trimmed_span(side, offset),
+        });
+    }
+    let argument_offset = offset + (side.len() - side.trim_start().len()) + trimmed.find('(').unwrap_or(0) + 1;
+
+    Ok((parse_column_ref(argument, which, argument_offset)?, Some(function.to_string())))
+}
+
+fn parse_column_ref(side: &str, which: &str, offset: usize) -> Result<ColumnRef, JoinerError> {
+    let span = trimmed_span(side, offset);
+    let side = side.trim();
+    let (table, column) = match side.split_once('.') {
+        Some(x) => x,
+        None => return Err(JoinerError::Syntax {
+            message: format!("missing '.' in {which} side '{side}', expected {INPUT_FORMAT}"),
+            span,
+        }),
+    };
+    if table.trim().is_empty() {
+        return Err(JoinerError::Syntax { message: format!("missing table name in {which} side '{side}'"), span });
+    }
+    if column.trim().is_empty() {
+        return Err(JoinerError::Syntax { message: format!("missing column name in {which} side '{side}'"), span });
+    }
+
+    Ok((table.trim().to_string(), column.trim().to_string()))
+}
+
+/// Parses a memory size in blocks such as `10000`, `64K` or `2M`, where the
+/// suffixes multiply by 1024 and 1024 * 1024 blocks respectively.
+pub fn parse_memory_size(input: &str) -> Result<u32, JoinerError> {
+    let input = input.trim();
+    let invalid = || JoinerError::Input(format!(
+        "invalid memory size '{input}', expected a whole number of blocks optionally followed by K or M"
+    ));
+    let (digits, multiplier) = match input.char_indices().last() {
+        Some((i, 'k' | 'K')) => (&input[..i], 1024),
+        Some((i, 'm' | 'M')) => (&input[..i], 1024 * 1024),
+        _ => (input, 1),
+    };
+    let blocks: u32 = match digits.parse() {
+        Ok(x) => x,
+        Err(_) => return Err(invalid()),
+    };
+
+    blocks.checked_mul(multiplier).ok_or_else(invalid)
+}
+
+/// Parses a table alias given as `alias=Table`, such as `o=Orders`.
+pub fn parse_alias(input: &str) -> Result<(String, String), JoinerError> {
+    match input.split_once('=') {
+        Some((alias, table)) if !alias.trim().is_empty() && !table.trim().is_empty() => {
+            Ok((alias.trim().to_string(), table.trim().to_string()))
+        }
+        _ => Err(JoinerError::Input(format!("invalid alias '{input}', expected alias=Table such as o=Orders"))),
+    }
+}
+
+/// Parses a size in bytes such as `8G`, `512M` or `65536`, where K, M, G
+/// and T (optionally followed by B) multiply by successive powers of 1024.
+pub fn parse_byte_size(input: &str) -> Result<u64, JoinerError> {
+    let input = input.trim();
+    let invalid = || JoinerError::Input(format!(
+        "invalid size '{input}', expected a whole number of bytes optionally followed by K, M, G or T"
+    ));
+    let number = input.strip_suffix(['b', 'B']).unwrap_or(input);
+    let (digits, power) = match number.char_indices().last() {
+        Some((i, 'k' | 'K')) => (&number[..i], 1),
+        Some((i, 'm' | 'M')) => (&number[..i], 2),
+        Some((i, 'g' | 'G')) => (&number[..i], 3),
+        Some((i, 't' | 'T')) => (&number[..i], 4),
+        _ => (number, 0),
+    };
+    let bytes: u64 = digits.parse().map_err(|_| invalid())?;
+
+    bytes.checked_mul(1024u64.pow(power)).ok_or_else(invalid)
+}
+
+/// Whole blocks of `block_bytes` bytes that fit in `bytes` bytes of memory.
+pub fn memory_blocks_from_bytes(bytes: u64, block_bytes: u32) -> Result<u32, JoinerError> {
+    if block_bytes == 0 {
+        return Err(JoinerError::Input(String::from("the block size has to be at least one byte")));
+    }
+    match u32::try_from(bytes / block_bytes as u64) {
+        Ok(0) => Err(JoinerError::Input(format!("{bytes} bytes of memory don't hold a single {block_bytes}-byte block"))),
+        Ok(blocks) => Ok(blocks),
+        Err(_) => Err(JoinerError::Input(format!("{bytes} bytes of memory are more than {} blocks", u32::MAX))),
+    }
+}
+
+/// Parses a memory sweep `start:end:step` into every size from `start` up
+/// to and including `end`, each part written like `parse_memory_size` takes it.
+pub fn parse_memory_sweep(input: &str) -> Result<Vec<u32>, JoinerError> {
+    let parts: Vec<&str> = input.split(':').collect();
+    let [start, end, step] = parts[..] else {
+        return Err(JoinerError::Input(format!("invalid memory sweep '{input}', expected start:end:step such as 10:200:10")));
+    };
+    let (start, end, step) = (parse_memory_size(start)?, parse_memory_size(end)?, parse_memory_size(step)?);
+    if step == 0 || start > end {
+        return Err(JoinerError::Input(format!(
+            "invalid memory sweep '{input}', the step must be positive and the start no larger than the end"
+        )));
+    }
+
+    Ok((start..=end).step_by(step as usize).collect())
+}
+
+/// Environment variable with the memory size to use when none is given on the command line.
+pub const MEMORY_ENV_VAR: &str = "SQL_JOINER_MEMORY";
+/// Memory size in blocks when neither the command line nor `MEMORY_ENV_VAR` gives one.
+pub const DEFAULT_MEMORY_SIZE: u32 = 10_000;
+
+/// Picks the memory size from the command line, then the value of
+/// `MEMORY_ENV_VAR`, then `DEFAULT_MEMORY_SIZE`, parsing either with
+/// `parse_memory_size`.
+pub fn resolve_memory_size(cli: Option<&str>, env: Option<&str>) -> Result<u32, JoinerError> {
+    match (cli, env) {
+        (Some(x), _) => parse_memory_size(x),
+        (None, Some(x)) => parse_memory_size(x).map_err(|err| match err {
+            JoinerError::Input(msg) => JoinerError::Input(format!("{MEMORY_ENV_VAR}: {msg}")),
+            err => err,
+        }),
+        (None, None) => Ok(DEFAULT_MEMORY_SIZE),
+    }
+}
+
+/// `memory_size` raised to `MIN_MEMORY_BLOCKS` when it's smaller, which no
+/// join method runs in, with a warning describing the change.
+pub fn clamp_memory_size(memory_size: u32) -> (u32, Option<String>) {
+    match memory_size < MIN_MEMORY_BLOCKS {
+        true => (MIN_MEMORY_BLOCKS, Some(format!(
+            "a memory size of {memory_size} blocks is below the {MIN_MEMORY_BLOCKS} every join method needs, using {MIN_MEMORY_BLOCKS}"
+        ))),
+        false => (memory_size, None),
+    }
+}
+
+/// Like `resolve_memory_size`, but the command line may give the memory in
+/// bytes instead, such as `8G`, which is divided into blocks of `block_bytes`.
+/// Giving it both in blocks and in bytes is an error.
+pub fn resolve_memory(cli_blocks: Option<&str>, cli_bytes: Option<&str>, block_bytes: u32, env: Option<&str>) -> Result<u32, JoinerError> {
+    match (cli_blocks, cli_bytes) {
+        (Some(_), Some(_)) => Err(JoinerError::Usage(String::from(
+            "the memory size is given both in blocks and with --memory-bytes, use only one"
+        ))),
+        (None, Some(bytes)) => memory_blocks_from_bytes(parse_byte_size(bytes)?, block_bytes),
+        (_, None) => resolve_memory_size(cli_blocks, env),
+    }
+}
@@ -0,0 +1,41 @@
+use std::{fmt, str::FromStr};
+
+use crate::JoinerError;
+
+/// Which rows of each side survive the join.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum JoinType {
+    #[default]
+    Inner,
+    LeftOuter,
+    RightOuter,
+    FullOuter,
+}
+
+impl FromStr for JoinType {
+    type Err = JoinerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "inner" => Ok(JoinType::Inner),
+            "left" | "left-outer" => Ok(JoinType::LeftOuter),
+            "right" | "right-outer" => Ok(JoinType::RightOuter),
+            "full" | "full-outer" => Ok(JoinType::FullOuter),
+            _ => Err(JoinerError::Input(format!(
+                "unknown join type '{s}', expected one of: inner, left, right, full"
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for JoinType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            JoinType::Inner => "inner",
+            JoinType::LeftOuter => "left outer",
+            JoinType::RightOuter => "right outer",
+            JoinType::FullOuter => "full outer",
+        };
+        f.pad(name)
+    }
+}
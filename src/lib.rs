@@ -0,0 +1,73 @@
+//! Estimates the block-transfer cost of joining two tables with the classic
+//! join algorithms (block nested-loop, indexed, merge and hash join), using
+//! table statistics loaded from a JSON metadata file.
+
+mod build_side;
+mod builder;
+mod cardinality;
+mod cost;
+mod cost_model;
+mod diff;
+mod error;
+mod estimate;
+mod generate;
+mod gzip;
+mod input;
+mod join_type;
+mod ordering;
+mod pseudocode;
+mod report;
+mod schema;
+mod selectivity_mode;
+mod yaml;
+
+pub use build_side::BuildSide;
+pub use builder::TableBuilder;
+pub use cardinality::{
+    comparison_selectivity, estimate_composite_join_cardinality, estimate_filtered_join_cardinality,
+    estimate_join_cardinality, histogram_selectivity, selectivity, RANGE_SELECTIVITY,
+};
+pub use cost::{
+    anti_join_cardinality, anti_join_cost, apply_sequential_discount, block_nested_fits,
+    block_nested_join_cost, block_nested_join_explain, block_nested_no_fit_cost,
+    block_nested_no_fit_explain, bloom_filter_false_positive_rate, default_index_fanout,
+    estimated_time_ms, hash_join_cost, hash_join_explain, height_of_index_tree,
+    hybrid_hash_join_cost, hybrid_hash_join_explain, index_fetch_cost, index_lookup_cost,
+    indexed_join_cost, indexed_join_explain, indexed_nested_loop_cost, indexed_nested_loop_explain,
+    key_width_factor, merge_join_cost, merge_join_explain, merge_rescan_cost, merge_sort_cost,
+    min_memory_for_hash, min_memory_for_one_pass_sort, nested_loop_join_cost,
+    nested_loop_join_explain, no_fit_reserved_buffers, output_blocks, scale_by_key_width,
+    semi_join_cost, sorting_cost, uncovered_rows_cost, BLOCK_SIZE, DEFAULT_IO_LATENCY_MS,
+    DEFAULT_RESERVED_BUFFERS, HASH_INDEX_LOOKUP_COST, INDEX_ENTRY_SIZE, MIN_MEMORY_BLOCKS,
+};
+pub use cost_model::{CostModel, DEFAULT_CPU_WEIGHT};
+pub use diff::{diff_metadata, MetadataChange};
+pub use error::JoinerError;
+pub use estimate::{
+    add_cpu_costs, assume_block_nested_no_fit, candidates_to_csv, cheapest_candidate,
+    choose_candidate, cost_ratio, discount_sequential_scans, estimate_best_join,
+    explain_block_nested_no_fit, explain_candidates, join_candidates, join_comparisons,
+    method_caveats, method_cost, sweep_memory, top_candidates, EstimateCache, JoinEstimate,
+    JoinMethod,
+};
+pub use generate::{generate_metadata, GenerateOptions};
+pub use input::{
+    check_non_interactive, clamp_memory_size, memory_blocks_from_bytes, parse_alias,
+    parse_byte_size, parse_conditions, parse_join_condition, parse_memory_size, parse_memory_sweep,
+    parse_predicate, parse_predicates, read_join_graph, read_predicates_file, read_user_input,
+    resolve_memory, resolve_memory_size, ColumnRef, Comparison, JoinCondition, NumberedPredicate,
+    Predicate, DEFAULT_MEMORY_SIZE, MEMORY_ENV_VAR,
+};
+pub use join_type::JoinType;
+pub use ordering::{best_join_order, JoinOrder, JoinStep, PlanNode, MAX_ORDERING_TABLES};
+pub use pseudocode::pseudocode;
+pub use report::{
+    join_report, JoinReport, ReportCandidate, ReportColumn, ReportCondition, ReportOptions,
+};
+pub use schema::{
+    check_distinct_counts, derive_block_count, deserialize_tables, find_column, find_table,
+    fold_case, format_schema, load_all, load_metadata, parse_tables, read_tables_from_file,
+    resolve_aliases, resolve_join, validate_tables, validation_issues, Column, IndexType,
+    ResolvedJoin, Table, TableStats, ValidationIssue, STDIN_PATH,
+};
+pub use selectivity_mode::SelectivityMode;
@@ -1,255 +1,661 @@
-use serde::Deserialize;
-use std::{cmp, env, fs, io, path::Path};
-
-#[derive(Deserialize, Debug)]
-struct Column {
-    name: String,
-    #[serde(default)]
-    indexed: bool,
-    total_values: u32
-}
+use joiner::{
+    best_join_order, candidates_to_csv, check_distinct_counts, check_non_interactive,
+    clamp_memory_size, cost_ratio, default_index_fanout, diff_metadata, estimated_time_ms,
+    fold_case, format_schema, generate_metadata, join_report, load_all, load_metadata,
+    min_memory_for_hash, min_memory_for_one_pass_sort, parse_alias, parse_byte_size,
+    parse_conditions, parse_memory_sweep, read_predicates_file, read_tables_from_file,
+    resolve_aliases, resolve_join, resolve_memory, sweep_memory, top_candidates, validate_tables,
+    validation_issues, BuildSide, CostModel, EstimateCache, GenerateOptions, JoinMethod, JoinReport,
+    JoinType, JoinerError, Predicate, ReportOptions, SelectivityMode, Table, BLOCK_SIZE,
+    DEFAULT_CPU_WEIGHT, DEFAULT_IO_LATENCY_MS, DEFAULT_RESERVED_BUFFERS, MEMORY_ENV_VAR,
+    RANGE_SELECTIVITY, STDIN_PATH,
+};
+use log::LevelFilter;
+use std::{cmp, env, fs, io, process, time::{SystemTime, UNIX_EPOCH}};
 
-#[derive(Deserialize, Debug)]
-#[serde(rename_all = "camelCase")]
-struct Table {
-    name: String,
-    columns: Vec<Column>,
-    sorted_column: Column,
-    nr: u32,
-    br: u32,
+#[derive(Clone, Copy, PartialEq)]
+enum OutputFormat {
+    Text,
+    Json,
+    Csv,
 }
 
-#[derive(Debug)]
-enum JoinerError {
-    IO(io::Error),
-    Parse(serde_json::Error),
+/// Settings shared by every predicate estimated in one run.
+struct Options {
+    /// How every join is estimated.
+    report: ReportOptions,
+    verbose: bool,
+    format: OutputFormat,
+    /// Also list this many of the cheapest methods, cheapest first.
+    top_k: Option<usize>,
+    /// Compare every other method's cost with this one's.
+    baseline: Option<JoinMethod>,
+    /// Print the pseudocode of the winning method.
+    pseudocode: bool,
 }
 
-impl From<io::Error> for JoinerError {
-    fn from(err: io::Error) -> Self {
-        JoinerError::IO(err)
-    }
+fn print_schema(tables: &[Table]) {
+    println!("TABLES =>");
+    print!("{}", format_schema(tables));
 }
 
-impl From<serde_json::Error> for JoinerError {
-    fn from(err: serde_json::Error) -> Self {
-        JoinerError::Parse(err)
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("error: {err}");
+        process::exit(1);
     }
 }
 
-fn load_json_from_file<P: AsRef<Path>>(path: P) -> Result<Vec<Table>, JoinerError> {
-    let content = fs::read_to_string(path)?;
-    let tables: Vec<Table> = serde_json::from_str(&content)?;
-
-    Ok(tables)
-}
-
-/*
- * The input format: <table1>.<column1> = <table2>.<column2>
- * For example,
- * Orders.cust_id = Customers.id
- */
-fn read_user_input() -> Result<((String, String), (String, String)), JoinerError> {
-    let mut buffer = String::new();
-    io::stdin().read_line(&mut buffer)?;
-
-    let halves: Vec<&str> = buffer.split("=").to_owned().collect();
-    let left: Vec<&str> = halves[0].trim().split(".").collect();
-    let right: Vec<&str> = halves[1].trim().split(".").collect();
-
-    let table1 = match left.get(0) {
-        Some(x) => x.to_string(),
-        None => panic!("Input format: <table1>.<column1> = <table2>.<column2>")
+fn run() -> Result<(), JoinerError> {
+    let args: Vec<String> = env::args().collect();
+    let binary = &args[0];
+    if args.get(1).is_some_and(|x| x == "generate") {
+        return generate(binary, &args[2..]);
+    }
+    let usage = format!(
+        "Usage: {binary} [order|validate|diff|generate] [--list-tables] [--repl] [--ignore-case] [--strict] [--verbose] [--explain] [--materialize] [--semi] [--anti] [--pseudocode] [--memory <blocks, e.g. 64K> | --memory-bytes <bytes, e.g. 8G>] [--block-bytes <bytes=4096>] [--format text|json|csv] [--join <predicate>]... [--type inner|left|right|full] [--method block|nested|indexed|indexed-nested|merge|hash|hybrid] [--build left|right|auto] [--predicates-file <path>] [--no-interactive] [--top-k <n>] [--baseline <method>] [--io-latency-ms <ms per block=10>] [--sequential-discount <fraction=0>] [--range-selectivity <fraction=0.333>] [--cost-model io|io+cpu] [--cpu-weight <blocks per comparison=0.001>] [--log-level off|error|warn|info|debug|trace] [--reserved-buffers <blocks=2>] [--assume-no-fit] [--alias <alias=Table>]... [--selectivity-mode distinct|fraction] [--sweep-memory <start:end:step>] [--metadata <path>]... <path to database metadata, or - for stdin, unless given with --metadata> <memory size=$SQL_JOINER_MEMORY or 10,000>? <index fan-out>?"
+    );
+    let mut verbose = false;
+    let mut explain = false;
+    let mut materialize = false;
+    let mut semi = false;
+    let mut anti = false;
+    let mut pseudocode = false;
+    let mut ignore_case = false;
+    let mut list_tables = false;
+    let mut repl = false;
+    let mut strict = false;
+    let mut assume_no_fit = false;
+    let mut no_interactive = false;
+    let mut format = OutputFormat::Text;
+    let mut joins: Vec<&String> = Vec::new();
+    let mut join_type = JoinType::Inner;
+    let mut memory: Option<&String> = None;
+    let mut memory_bytes: Option<&String> = None;
+    let mut block_bytes: Option<u32> = None;
+    let mut paths: Vec<&String> = Vec::new();
+    let mut method: Option<JoinMethod> = None;
+    let mut build = BuildSide::Auto;
+    let mut selectivity_mode = SelectivityMode::Distinct;
+    let mut predicates_file: Option<&String> = None;
+    let mut top_k: Option<usize> = None;
+    let mut baseline: Option<JoinMethod> = None;
+    let mut io_latency_ms = DEFAULT_IO_LATENCY_MS;
+    let mut sequential_discount = 0.0;
+    let mut range_selectivity = RANGE_SELECTIVITY;
+    let mut cost_model = CostModel::Io;
+    let mut cpu_weight = DEFAULT_CPU_WEIGHT;
+    let mut log_level = LevelFilter::Off;
+    let mut reserved_buffers = DEFAULT_RESERVED_BUFFERS;
+    let mut aliases: Vec<(String, String)> = Vec::new();
+    let mut sweep: Option<Vec<u32>> = None;
+    let mut positional: Vec<&String> = Vec::new();
+    let mut rest = args[1..].iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--verbose" | "-v" => verbose = true,
+            "--explain" => explain = true,
+            "--materialize" => materialize = true,
+            "--semi" => semi = true,
+            "--anti" => anti = true,
+            "--pseudocode" => pseudocode = true,
+            "--ignore-case" => ignore_case = true,
+            "--list-tables" => list_tables = true,
+            "--repl" => repl = true,
+            "--strict" => strict = true,
+            "--assume-no-fit" => assume_no_fit = true,
+            "--no-interactive" => no_interactive = true,
+            "--format" => format = match rest.next().map(String::as_str) {
+                Some("text") => OutputFormat::Text,
+                Some("json") => OutputFormat::Json,
+                Some("csv") => OutputFormat::Csv,
+                _ => return Err(JoinerError::Usage(String::from("--format expects one of: text, json, csv"))),
+            },
+            "--join" | "-j" => match rest.next() {
+                Some(x) => joins.push(x),
+                None => return Err(JoinerError::Usage(String::from(
+                    "--join expects a predicate such as \"Orders.cust_id = Customers.id\""
+                ))),
+            },
+            "--memory-bytes" => memory_bytes = match rest.next() {
+                Some(x) => Some(x),
+                None => return Err(JoinerError::Usage(String::from(
+                    "--memory-bytes expects a number of bytes such as 8G or 512M"
+                ))),
+            },
+            "--block-bytes" => block_bytes = match rest.next().map(|x| x.parse()) {
+                Some(Ok(x)) if x > 0 => Some(x),
+                _ => return Err(JoinerError::Usage(String::from(
+                    "--block-bytes expects a positive number of bytes per block such as 4096"
+                ))),
+            },
+            "--memory" | "-m" => memory = match rest.next() {
+                Some(x) => Some(x),
+                None => return Err(JoinerError::Usage(String::from(
+                    "--memory expects a number of blocks such as 10000, 64K or 2M"
+                ))),
+            },
+            "--method" => method = match rest.next() {
+                Some(x) => Some(x.parse()?),
+                None => return Err(JoinerError::Usage(String::from(
+                    "--method expects one of: block, nested, indexed, indexed-nested, merge, hash, hybrid"
+                ))),
+            },
+            "--build" => build = match rest.next() {
+                Some(x) => x.parse()?,
+                None => return Err(JoinerError::Usage(String::from("--build expects one of: left, right, auto"))),
+            },
+            "--selectivity-mode" => selectivity_mode = match rest.next() {
+                Some(x) => x.parse()?,
+                None => return Err(JoinerError::Usage(String::from(
+                    "--selectivity-mode expects one of: fraction, distinct"
+                ))),
+            },
+            "--predicates-file" => predicates_file = match rest.next() {
+                Some(x) => Some(x),
+                None => return Err(JoinerError::Usage(String::from("--predicates-file expects a path"))),
+            },
+            "--top-k" => top_k = match rest.next().map(|x| x.parse()) {
+                Some(Ok(x)) if x > 0 => Some(x),
+                _ => return Err(JoinerError::Usage(String::from("--top-k expects a positive number of methods"))),
+            },
+            "--baseline" => baseline = match rest.next() {
+                Some(x) => Some(x.parse()?),
+                None => return Err(JoinerError::Usage(String::from(
+                    "--baseline expects one of: block, nested, indexed, indexed-nested, merge, hash, hybrid"
+                ))),
+            },
+            "--io-latency-ms" => io_latency_ms = match rest.next().map(|x| x.parse::<f64>()) {
+                Some(Ok(x)) if x.is_finite() && x >= 0.0 => x,
+                _ => return Err(JoinerError::Usage(String::from(
+                    "--io-latency-ms expects a non-negative number of milliseconds per block"
+                ))),
+            },
+            "--sequential-discount" => sequential_discount = match rest.next().map(|x| x.parse::<f64>()) {
+                Some(Ok(x)) if (0.0..1.0).contains(&x) => x,
+                _ => return Err(JoinerError::Usage(String::from(
+                    "--sequential-discount expects a fraction from 0 up to but not including 1, such as 0.3"
+                ))),
+            },
+            "--range-selectivity" => range_selectivity = match rest.next().map(|x| x.parse::<f64>()) {
+                Some(Ok(x)) if (0.0..=1.0).contains(&x) => x,
+                _ => return Err(JoinerError::Usage(String::from(
+                    "--range-selectivity expects a fraction from 0 to 1, such as 0.25"
+                ))),
+            },
+            "--cost-model" => cost_model = match rest.next() {
+                Some(x) => x.parse()?,
+                None => return Err(JoinerError::Usage(String::from("--cost-model expects one of: io, io+cpu"))),
+            },
+            "--cpu-weight" => cpu_weight = match rest.next().map(|x| x.parse::<f64>()) {
+                Some(Ok(x)) if x.is_finite() && x >= 0.0 => x,
+                _ => return Err(JoinerError::Usage(String::from(
+                    "--cpu-weight expects a non-negative number of blocks per comparison, such as 0.001"
+                ))),
+            },
+            "--log-level" => log_level = match rest.next().map(|x| x.parse()) {
+                Some(Ok(x)) => x,
+                _ => return Err(JoinerError::Usage(String::from(
+                    "--log-level expects one of: off, error, warn, info, debug, trace"
+                ))),
+            },
+            "--reserved-buffers" => reserved_buffers = match rest.next().map(|x| x.parse()) {
+                Some(Ok(x)) => x,
+                _ => return Err(JoinerError::Usage(String::from(
+                    "--reserved-buffers expects a number of blocks such as 2"
+                ))),
+            },
+            "--sweep-memory" => sweep = match rest.next() {
+                Some(x) => Some(parse_memory_sweep(x)?),
+                None => return Err(JoinerError::Usage(String::from(
+                    "--sweep-memory expects start:end:step such as 10:200:10"
+                ))),
+            },
+            "--alias" => match rest.next() {
+                Some(x) => aliases.push(parse_alias(x)?),
+                None => return Err(JoinerError::Usage(String::from("--alias expects alias=Table such as o=Orders"))),
+            },
+            "--metadata" => match rest.next() {
+                Some(x) => paths.push(x),
+                None => return Err(JoinerError::Usage(String::from("--metadata expects a path"))),
+            },
+            "--type" => join_type = match rest.next() {
+                Some(x) => x.parse()?,
+                None => return Err(JoinerError::Usage(String::from(
+                    "--type expects one of: inner, left, right, full"
+                ))),
+            },
+            _ => positional.push(arg),
+        }
+    }
+    if baseline.is_some() && method.is_some() {
+        return Err(JoinerError::Usage(String::from(
+            "--baseline compares every method, so it can't be combined with --method"
+        )));
+    }
+    if sweep.is_some() && assume_no_fit {
+        return Err(JoinerError::Usage(String::from(
+            "--assume-no-fit depends on the memory size, so it can't be combined with --sweep-memory"
+        )));
+    }
+    if sweep.is_some() && method.is_some() {
+        return Err(JoinerError::Usage(String::from(
+            "--sweep-memory picks the best method at every size, so it can't be combined with --method"
+        )));
+    }
+    // Logs go to stderr, and only at the level asked for.
+    env_logger::Builder::new().filter_level(log_level).init();
+    let order_mode = positional.first().is_some_and(|x| *x == "order");
+    let validate_mode = positional.first().is_some_and(|x| *x == "validate");
+    let diff_mode = positional.first().is_some_and(|x| *x == "diff");
+    if order_mode || validate_mode || diff_mode {
+        positional.remove(0);
+    }
+    if order_mode && assume_no_fit {
+        return Err(JoinerError::Usage(String::from(
+            "--assume-no-fit only applies to a single join, so it can't be combined with order"
+        )));
+    }
+    if diff_mode {
+        return match positional[..] {
+            [old, new] => diff(old, new, selectivity_mode),
+            _ => Err(JoinerError::Usage(format!("Usage: {binary} diff <old metadata> <new metadata>"))),
+        };
+    }
+    // Without --metadata the first positional argument is the path.
+    if paths.is_empty() {
+        if positional.is_empty() {
+            return Err(JoinerError::Usage(usage));
+        }
+        paths.push(positional.remove(0));
+    }
+    if validate_mode {
+        return validate(&paths, selectivity_mode);
+    }
+    // A predicates file or a schema listing never reads a predicate.
+    if no_interactive && predicates_file.is_none() && !list_tables {
+        check_non_interactive(joins.first().map(|x| x.as_str()), repl)?;
+    }
+    // The positional memory size predates --memory, which wins when both are given.
+    let cli_memory = memory.or(positional.first().copied()).map(String::as_str);
+    let memory_bytes = memory_bytes.map(String::as_str);
+    let memory_size = resolve_memory(
+        cli_memory, memory_bytes, block_bytes.unwrap_or(BLOCK_SIZE), env::var(MEMORY_ENV_VAR).ok().as_deref()
+    )?;
+    // Without --block-bytes, every join divides the memory into blocks of its own tables' size.
+    let memory_bytes = match (memory_bytes, block_bytes) {
+        (Some(x), None) => Some(parse_byte_size(x)?),
+        _ => None,
     };
-    let column1 = match left.get(1) {
-        Some(x) => x.to_string(),
-        None => panic!("Input format: <table1>.<column1> = <table2>.<column2>")
+    let (memory_size, warning) = clamp_memory_size(memory_size);
+    // Joins that divide the memory by their own block size warn about it in their report instead.
+    if let Some(warning) = warning.filter(|_| memory_bytes.is_none() || order_mode) {
+        eprintln!("warning: {warning}");
+    }
+    if memory_size <= reserved_buffers {
+        return Err(JoinerError::Usage(format!(
+            "--reserved-buffers ({reserved_buffers}) must be less than the memory size ({memory_size} blocks)"
+        )));
+    }
+    let index_fanout: u32 = match positional.get(1) {
+        Some(x) => match x.parse() {
+            Ok(xx) if xx >= 2 => xx,
+            _ => return Err(JoinerError::Usage(String::from(
+                "Index fan-out should be a whole number of at least 2"
+            )))
+        },
+        None => default_index_fanout(BLOCK_SIZE),
     };
-    let table2 = match right.get(0) {
-        Some(x) => x.to_string(),
-        None => panic!("Input format: <table1>.<column1> = <table2>.<column2>")
+
+    let from_stdin = paths.iter().any(|x| x.as_str() == STDIN_PATH);
+    if from_stdin && repl {
+        return Err(JoinerError::Usage(String::from(
+            "metadata can't be read from stdin in --repl mode, which reads predicates from stdin"
+        )));
+    }
+    if from_stdin && joins.is_empty() && !list_tables {
+        return Err(JoinerError::Usage(String::from(
+            "metadata is read from stdin, so the join predicate must be given with --join"
+        )));
+    }
+
+    let mut data = load_all(&paths, selectivity_mode)?;
+    if ignore_case {
+        fold_case(&mut data);
+        validate_tables(&data)?;
+    }
+    // Predicates are folded the same way before they're parsed.
+    let fold = |predicate: &str| match ignore_case {
+        true => predicate.to_lowercase(),
+        false => predicate.to_string(),
     };
-    let column2 = match right.get(1) {
-        Some(x) => x.to_string(),
-        None => panic!("Input format: <table1>.<column1> = <table2>.<column2>")
+    for warning in check_distinct_counts(&mut data, strict)? {
+        eprintln!("warning: {warning}, clamping to the row count");
+    }
+    let aliases: Vec<(String, String)> = aliases.iter().map(|(alias, table)| (fold(alias), fold(table))).collect();
+    // Checks the aliases themselves before any predicate uses them.
+    resolve_aliases(&data, &[], &aliases)?;
+
+    if list_tables {
+        print_schema(&data);
+        return Ok(());
+    }
+    if format == OutputFormat::Text {
+        print_schema(&data);
+    }
+
+    if order_mode {
+        if joins.len() > 1 {
+            return Err(JoinerError::Usage(String::from("order finds the join order of a single predicate, give --join once")));
+        }
+        let conditions = match joins.first() {
+            Some(x) => parse_conditions(&fold(x))?,
+            None => parse_conditions(&fold(&read_predicate()?))?,
+        };
+        let conditions = resolve_aliases(&data, &conditions, &aliases)?;
+        let plan = best_join_order(&data, &conditions, memory_size, index_fanout, build, reserved_buffers, materialize)?;
+        if format == OutputFormat::Json {
+            println!("{}", serde_json::to_string_pretty(&plan.plan_tree())?);
+            return Ok(());
+        }
+        let mut joined = plan.first.clone();
+        for step in &plan.steps {
+            joined = format!("{joined} X {}", step.table);
+        }
+        println!("Join order: {joined}");
+        for (i, step) in plan.steps.iter().enumerate() {
+            println!(
+                " {}. X {}: {} blocks by using method {}, ~{} rows",
+                i + 1, step.table, step.cost, step.method, step.estimated_rows
+            );
+        }
+        println!("Total cost for joining is {} blocks", plan.total_cost);
+        print!("Plan:\n{}", plan.plan_tree());
+        return Ok(());
+    }
+
+    let report = ReportOptions {
+        memory_size, memory_bytes, index_fanout, join_type, explain, method, materialize, semi, anti, build, io_latency_ms,
+        reserved_buffers, assume_no_fit, sequential_discount, aliases, cost_model, cpu_weight, range_selectivity,
     };
+    let options = Options { report, verbose, format, top_k, baseline, pseudocode };
+    if let Some(path) = predicates_file {
+        return batch(&data, path, &options);
+    }
+    if repl {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if io::stdin().read_line(&mut line)? == 0 || line.trim().is_empty() {
+                return Ok(());
+            }
+            if let Err(err) = fold(&line).parse().and_then(|x| report_join(&data, &x, &options)) {
+                eprintln!("error: {err}");
+            }
+        }
+    }
+
+    if joins.is_empty() {
+        let predicate: Predicate = fold(&read_predicate()?).parse()?;
+        return estimate_predicate(&data, &predicate, sweep.as_deref(), &options);
+    }
+    for (i, join) in joins.iter().enumerate() {
+        // A single predicate is printed as it always was.
+        if joins.len() > 1 && format == OutputFormat::Text {
+            if i > 0 {
+                println!();
+            }
+            println!("=== Join {} of {}: {join} ===", i + 1, joins.len());
+        }
+        estimate_predicate(&data, &fold(join).parse()?, sweep.as_deref(), &options)?;
+    }
 
-    Ok(((table1, column1), (table2, column2)))
+    Ok(())
 }
 
-fn height_of_index_tree(n: u32, k: u32) -> u32 {
-    ((k as f32).log2() / ((n/2) as f32).log2()).ceil() as u32
+/// Prints the estimate of one predicate, or its best method at every memory size of `sweep`.
+fn estimate_predicate(data: &[Table], predicate: &Predicate, sweep: Option<&[u32]>, options: &Options) -> Result<(), JoinerError> {
+    match sweep {
+        Some(memory_sizes) => print_memory_sweep(data, predicate, memory_sizes, options),
+        None => report_join(data, predicate, options),
+    }
 }
 
-fn block_nested_join_cost(table1: &Table, table2: &Table, memory_size: u32) -> u32 {
-    let smaller: u32 = cmp::min(table1.br, table2.br);
-    if smaller < memory_size {
-        table1.br + table2.br
-    } else {
-        smaller * (table1.br + table2.br - smaller + 1)
+/// Prints the best method and cost at every memory size of the sweep.
+fn print_memory_sweep(data: &[Table], predicate: &Predicate, memory_sizes: &[u32], options: &Options) -> Result<(), JoinerError> {
+    let ReportOptions { index_fanout, build, reserved_buffers, .. } = options.report;
+    let conditions = resolve_aliases(data, &predicate.conditions, &options.report.aliases)?;
+    let join = resolve_join(data, &conditions)?;
+    let sweep = sweep_memory(&join, memory_sizes, index_fanout, build, reserved_buffers);
+
+    println!("{:>12}  {:<26}{:>12}", "Memory", "Best method", "Cost");
+    for (memory_size, best) in sweep {
+        match best {
+            Some((method, cost)) => println!("{memory_size:>12}  {method:<26}{cost:>12}"),
+            None => println!("{memory_size:>12}  {:<26}{:>12}", "N/A", "N/A"),
+        }
     }
-} 
 
-fn indexed_join_cost(table1: &Table, column1: &Column, table2: &Table, column2: &Column) -> Option<u32> {
-    let n = 10;
-    let mut cost: Option<u32> = None;
-    if column1.indexed {
-        let lookup_cost1: u32 = height_of_index_tree(n, column1.total_values);
-        let total_cost1: u32 = table2.nr * lookup_cost1 + table2.br;
-        cost = match cost {
-            None => Some(total_cost1),
-            Some(x) => Some(cmp::min(x, total_cost1))
+    Ok(())
+}
+
+/// Reads the join predicate from the first line of stdin.
+fn read_predicate() -> Result<String, JoinerError> {
+    let mut line = String::new();
+    io::stdin().read_line(&mut line)?;
+
+    Ok(line)
+}
+
+/// Writes random metadata to stdout or the file given with --output.
+fn generate(binary: &str, args: &[String]) -> Result<(), JoinerError> {
+    let mut options = GenerateOptions::default();
+    let mut output: Option<&String> = None;
+    let count = |flag: &str, value: Option<&String>| match value.map(|x| x.parse::<f64>()) {
+        // Accepts counts written like 1e6.
+        Some(Ok(x)) if x >= 1.0 && x <= u32::MAX as f64 && x.fract() == 0.0 => Ok(x as u32),
+        _ => Err(JoinerError::Usage(format!("{flag} expects a positive whole number such as 10 or 1e6"))),
+    };
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--tables" => options.tables = count("--tables", rest.next())?,
+            "--max-columns" => options.max_columns = count("--max-columns", rest.next())?,
+            "--max-rows" => options.max_rows = count("--max-rows", rest.next())?,
+            "--output" | "-o" => output = match rest.next() {
+                Some(x) => Some(x),
+                None => return Err(JoinerError::Usage(String::from("--output expects a path"))),
+            },
+            _ => return Err(JoinerError::Usage(format!(
+                "Usage: {binary} generate [--tables <n=10>] [--max-columns <n=8>] [--max-rows <n=1e6>] [--output <path>]"
+            ))),
         }
     }
-    if column2.indexed {
-        let lookup_cost2: u32 = height_of_index_tree(n, column2.total_values);
-        let total_cost2: u32 = table1.nr * lookup_cost2 + table1.br;
-        cost = match cost {
-            None => Some(total_cost2),
-            Some(x) => Some(cmp::min(x, total_cost2))
+
+    let seed = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |x| x.as_nanos() as u64);
+    let metadata = serde_json::to_string_pretty(&generate_metadata(&options, seed))?;
+    match output {
+        Some(path) => fs::write(path, metadata + "\n").map_err(|err| JoinerError::from(err).with_path(path)),
+        None => {
+            println!("{metadata}");
+            Ok(())
         }
     }
-    
-    cost
 }
 
-fn sorting_cost(br: u32, memory_size: u32) -> u32 {
-    let tmp = ((br / memory_size) as f32).ceil();
-    2 * br * (tmp as f32).log((memory_size - 1) as f32).ceil() as u32
+/// Prints a changelog of what differs between two metadata files.
+fn diff(old: &str, new: &str, mode: SelectivityMode) -> Result<(), JoinerError> {
+    let changes = diff_metadata(&load_metadata(old, mode)?, &load_metadata(new, mode)?);
+    if changes.is_empty() {
+        println!("No changes");
+    }
+    for change in &changes {
+        println!("{change}");
+    }
+
+    Ok(())
 }
 
-fn merge_join_cost(table1: &Table, column1: &Column, table2: &Table, column2: &Column, memory_size: u32) -> u32 {
-    let mut cost_to_sort: u32 = 0;
-    if table1.sorted_column.name != column1.name {
-        cost_to_sort += sorting_cost(table1.br, memory_size);
+/// Reports every consistency problem in the metadata instead of stopping at the first.
+fn validate(paths: &[&String], mode: SelectivityMode) -> Result<(), JoinerError> {
+    let mut tables: Vec<Table> = Vec::new();
+    for path in paths {
+        tables.extend(read_tables_from_file(path, mode)?);
+    }
+
+    let issues = validation_issues(&tables);
+    if issues.is_empty() {
+        println!("Metadata is valid: {} tables", tables.len());
+        return Ok(());
     }
-    if table2.sorted_column.name != column2.name {
-        cost_to_sort += sorting_cost(table2.br, memory_size);
+    for issue in &issues {
+        println!("{issue}");
     }
-    
-    cost_to_sort + table1.br + table2.br
+    Err(JoinerError::Input(format!("found {} problems in the metadata", issues.len())))
 }
 
-fn hash_join_cost(table1: &Table, table2: &Table, memory_size: u32) -> Option<u32> {
-    let smaller: &Table = cmp::min_by_key(table1, table2, |x: &&Table| x.br); 
-    if memory_size * memory_size > smaller.br {
-        let nh: u32 = ((smaller.br / memory_size) as f32).ceil() as u32 + 1;
-        return Some(3 * (table1.br + table2.br) + nh)
-    } 
-    None
+/// Prints the best method for every predicate in a batch file, one line each.
+fn batch(data: &[Table], path: &str, options: &Options) -> Result<(), JoinerError> {
+    let mut cache = EstimateCache::new();
+    for (line, predicate) in read_predicates_file(path)? {
+        let best = predicate.and_then(|conditions| {
+            let ReportOptions {
+                memory_size, index_fanout, join_type, method, materialize, build, reserved_buffers, assume_no_fit,
+                sequential_discount, cost_model, memory_bytes, range_selectivity, ..
+            } = options.report;
+            // Forced methods, materialized results, block nested loop assumed not to fit, discounts, CPU costs,
+            // memory in bytes and other range selectivities aren't part of a cached estimate.
+            let cacheable = method.is_none() && !materialize && !assume_no_fit && sequential_discount == 0.0
+                && memory_bytes.is_none() && range_selectivity == RANGE_SELECTIVITY;
+            if cacheable && cost_model == CostModel::Io {
+                let conditions = resolve_aliases(data, &conditions, &options.report.aliases)?;
+                let join = resolve_join(data, &conditions)?;
+                let estimate = cache.estimate(&join, join_type, memory_size, index_fanout, build, reserved_buffers)?;
+                return Ok((estimate.method, estimate.cost_blocks, estimate.estimated_rows));
+            }
+            // A batch line only shows the best method.
+            let report_options = ReportOptions { explain: false, semi: false, anti: false, ..options.report.clone() };
+            let report = join_report(data, &Predicate { conditions }, &report_options)?;
+            Ok((report.best_method, report.best_cost, report.estimated_rows))
+        });
+        match best {
+            Ok((method, cost, rows)) => println!("line {line}: {cost} blocks by using method {method}, ~{rows} rows"),
+            Err(err) => println!("line {line}: error: {err}"),
+        }
+    }
+
+    Ok(())
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
-    let binary = &args[0];
-    let path = match args.get(1) {
-        Some(x) => x,
-        None => panic!("Usage: {binary} <path to database metadata> <memory size=10,000>?"),
-    };
-    let memory_size: u32 = match args.get(2) {
-        Some(x) => match x.parse() {
-            Ok(xx) => xx,
-            Err(_) => panic!("Memory size should be a whole number")
-        },
-        None => 10_000,
-    };
+/// Estimates every join method for one predicate and prints the result.
+fn report_join(data: &[Table], predicate: &Predicate, options: &Options) -> Result<(), JoinerError> {
+    let Options { verbose, format, top_k, baseline, pseudocode, .. } = *options;
+    let ReportOptions { index_fanout, join_type, explain, method, build, io_latency_ms, .. } = options.report;
+    let report = join_report(data, predicate, &options.report)?;
+    let memory_size = report.memory_size;
+    for warning in &report.warnings {
+        eprintln!("warning: {warning}");
+    }
+    let JoinReport { best_method, best_cost, estimated_rows: cardinality, semi_join_cost: semi_join, anti_join_cost: anti_join, anti_join_rows, .. } = report;
+    let candidates = report.costs();
+    // The report names the methods, the text output also names the tables.
+    let conditions = resolve_aliases(data, &predicate.conditions, &options.report.aliases)?;
+    let join = resolve_join(data, &conditions)?;
 
-    let data = match load_json_from_file(path) {
-        Ok(x) => x,
-        Err(JoinerError::IO(err)) => panic!("IO error {err}"),
-        Err(JoinerError::Parse(err)) => panic!("Parse error {err}"),
-    };
+    if format == OutputFormat::Csv {
+        print!("{}", candidates_to_csv(&candidates, cardinality));
+        return Ok(());
+    }
+    if format == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
 
-    println!("TABLES =>");
-    for table in &data {
-        println!("{}", table.name);
-        for column in &table.columns {
-            println!(" - {}", column.name);
+    println!("Memory size: {memory_size}");
+    println!("Index fan-out: {index_fanout}");
+    println!("User entered: {predicate}");
+    println!("Join type: {join_type}");
+
+    if verbose {
+        println!();
+        println!("{:<26}{:>12}", "Method", "Cost");
+        for (method, cost) in &candidates {
+            let cost = match cost {
+                Some(x) => x.to_string(),
+                None => String::from("N/A"),
+            };
+            let marker = if *method == best_method { " <- best" } else { "" };
+            println!("{method:<26}{cost:>12}{marker}");
+        }
+        println!();
+        let build_br = match build {
+            BuildSide::Auto => cmp::min(join.table1.br, join.table2.br),
+            BuildSide::Left => join.table1.br,
+            BuildSide::Right => join.table2.br,
+        };
+        println!("Memory for hash partitions without recursion: {} blocks", min_memory_for_hash(build_br));
+        for table in [join.table1, join.table2] {
+            println!("Memory for a one-pass sort of {}: {} blocks", table.name, min_memory_for_one_pass_sort(table.br));
         }
         println!();
     }
-
-    let ((table1_name, column1_name), (table2_name, column2_name)) = match read_user_input() {
-        Ok(x) => x,
-        Err(JoinerError::IO(err)) => panic!("Error reading user input {err}"),
-        Err(JoinerError::Parse(err)) => panic!("Error reading user input {err}"),
-    };
-
-    let (mut table1, mut table2): (Option<&Table>, Option<&Table>) = (None, None);
-    let (column1, column2): (Option<&Column>, Option<&Column>);
-    for table in &data {
-        if table.name == table1_name {
-            table1 = Some(table);
-        } else if table.name == table2_name {
-            table2 = Some(table);
-        }        
-    }
-
-    column1 = match table1 {
-        None => panic!("Table not found with name {table1_name}"),
-        Some(t) => {
-            let mut ret_val: Option<&Column> = None;
-            for column in &t.columns {
-                if column.name == column1_name {
-                    ret_val = Some(column);
-                }
-            }
-            ret_val
+    if explain {
+        if !verbose {
+            println!();
         }
-    };
-    column2 = match table2 {
-        None => panic!("Table not found with name {table2_name}"),
-        Some(t) => {
-            let mut ret_val: Option<&Column> = None;
-            for column in &t.columns {
-                if column.name == column2_name {
-                    ret_val = Some(column);
-                }
-            }
-            ret_val
+        for explanation in report.candidates.iter().filter_map(|x| x.explanation.as_ref()) {
+            println!("{explanation}");
         }
-    };
-
-    if column1.is_none() {
-        panic!("Column {column1_name} not found in table {table1_name}");
-    }
-    if column2.is_none() {
-        panic!("Column {column2_name} not found in table {table2_name}");
-    }
-    
-    let table1 = table1.unwrap();
-    let table2 = table2.unwrap();
-    let column1 = column1.unwrap();
-    let column2 = column2.unwrap();
-    let mut best_method: String = String::from("Block Nested Join");
-    let mut best_cost: u32 = block_nested_join_cost(&table1, &table2, memory_size);
-    best_cost = match indexed_join_cost(&table1, &column1, &table2, &column2) {
-        None => best_cost,
-        Some(x) => {
-            if x < best_cost {
-                best_method = String::from("Indexed Join");
-            }
-            cmp::min(best_cost, x)
+        println!();
+    }
+    if let Some(k) = top_k {
+        println!("Cheapest methods:");
+        for (i, (method, cost)) in top_candidates(&candidates, k).iter().enumerate() {
+            println!(" {}. {method}: {cost} blocks", i + 1);
         }
-    };
-
-    let merge_cost = merge_join_cost(&table1, &column1, &table2, &column2, memory_size);
-    if merge_cost < best_cost {
-        best_cost = merge_cost;
-        best_method = String::from("Merge Join");
+        println!();
     }
-    best_cost = match hash_join_cost(&table1, &table2, memory_size) {
-        None => best_cost,
-        Some(x) => {
-            if x < best_cost {
-                best_method = String::from("Hash Join");
-            }
-            cmp::min(best_cost, x)
+    if let Some(baseline) = baseline {
+        let baseline_cost = match candidates.iter().find(|(x, _)| *x == baseline) {
+            Some((_, Some(x))) => *x,
+            _ => return Err(JoinerError::Input(format!("baseline {baseline} isn't applicable to this join"))),
+        };
+        println!("Compared with {baseline} ({baseline_cost} blocks):");
+        for (method, cost) in candidates.iter().filter(|(x, _)| *x != baseline) {
+            let cost = match cost {
+                Some(x) => *x,
+                None => continue,
+            };
+            let comparison = match cost_ratio(cost, baseline_cost) {
+                None => format!("{method} can't be compared with a baseline that costs nothing"),
+                Some(_) if cost == 0 => format!("{method} costs nothing"),
+                Some(x) if x < 1.0 => format!("{method} is {:.1}x cheaper", 1.0 / x),
+                Some(x) if x > 1.0 => format!("{method} is {x:.1}x more expensive"),
+                Some(_) => format!("{method} is as expensive"),
+            };
+            println!(" {comparison}");
         }
-    };
-
-    println!("Memory size: {memory_size}");
-    println!("User entered: {table1_name}.{column1_name} X {table2_name}.{column2_name}");
+        println!();
+    }
+    match method {
+        Some(_) => println!("Cost for joining is {best_cost} blocks by using method {best_method}"),
+        None => println!("Best cost for joining is {best_cost} blocks by using method {best_method}"),
+    }
+    println!("{best_cost} blocks ≈ {:.1} ms", estimated_time_ms(best_cost, io_latency_ms));
+    println!("Estimated result size is {cardinality} rows");
+    if let Some(cost) = semi_join {
+        println!("Semi-join of {} with a bloom filter over {} costs {cost} blocks", join.table1.name, join.table2.name);
+    }
+    if let (Some(cost), Some(rows)) = (anti_join, anti_join_rows) {
+        println!(
+            "Anti-join keeping the rows of {} without a match in {} costs {cost} blocks, ~{rows} rows",
+            join.table1.name, join.table2.name
+        );
+    }
+    if pseudocode {
+        println!();
+        println!("{}", joiner::pseudocode(best_method));
+    }
 
-    println!("Best cost for joining is {best_cost} blocks by using method {best_method}");
+    Ok(())
 }
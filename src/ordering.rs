@@ -0,0 +1,272 @@
+use serde::Serialize;
+use std::fmt;
+
+use crate::{
+    cheapest_candidate, estimate_filtered_join_cardinality, find_column, find_table,
+    join_candidates, output_blocks, BuildSide, Column, Comparison, JoinCondition, JoinMethod,
+    JoinType, JoinerError, ResolvedJoin, Table, MIN_MEMORY_BLOCKS, RANGE_SELECTIVITY,
+};
+
+/// Enumerating left-deep orders is factorial in the number of tables.
+pub const MAX_ORDERING_TABLES: usize = 6;
+
+/// One join of a left-deep plan: the result accumulated so far joined with `table`.
+#[derive(Debug, Clone)]
+pub struct JoinStep {
+    pub table: String,
+    pub method: JoinMethod,
+    pub cost: u64,
+    pub estimated_rows: u64,
+}
+
+/// A left-deep join order starting from `first`, with the cost of every step.
+#[derive(Debug, Clone)]
+pub struct JoinOrder {
+    pub first: String,
+    pub steps: Vec<JoinStep>,
+    pub total_cost: u64,
+}
+
+/// A node of a join plan: a table read as it is, or the join of two subplans.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PlanNode {
+    Scan { table: String },
+    Join {
+        method: JoinMethod,
+        /// Cost of this join alone.
+        cost: u64,
+        /// Cost of this join and every join below it.
+        total_cost: u64,
+        estimated_rows: u64,
+        left: Box<PlanNode>,
+        right: Box<PlanNode>,
+    },
+}
+
+impl JoinOrder {
+    /// The order as a left-deep tree: every step joins the tree of the steps
+    /// before it with a scan of its table, so the root is the last step.
+    pub fn plan_tree(&self) -> PlanNode {
+        let mut tree = PlanNode::Scan { table: self.first.clone() };
+        let mut total_cost: u64 = 0;
+        for step in &self.steps {
+            total_cost += step.cost;
+            tree = PlanNode::Join {
+                method: step.method,
+                cost: step.cost,
+                total_cost,
+                estimated_rows: step.estimated_rows,
+                left: Box::new(tree),
+                right: Box::new(PlanNode::Scan { table: step.table.clone() }),
+            };
+        }
+
+        tree
+    }
+}
+
+/// One line per node, children indented two spaces under their join, the
+/// left input first.
+impl fmt::Display for PlanNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_indented(f, 0)
+    }
+}
+
+impl PlanNode {
+    fn write_indented(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+        let indent = "  ".repeat(depth);
+        match self {
+            PlanNode::Scan { table } => writeln!(f, "{indent}{table}"),
+            PlanNode::Join { method, cost, total_cost, estimated_rows, left, right } => {
+                writeln!(f, "{indent}{method}: {cost} blocks, {total_cost} in total, ~{estimated_rows} rows")?;
+                left.write_indented(f, depth + 1)?;
+                right.write_indented(f, depth + 1)
+            }
+        }
+    }
+}
+
+/// Enumerates the left-deep orders of the tables named in `conditions` and
+/// returns the cheapest one, skipping orders that would need a cross product.
+/// Every step is costed like `join_candidates` does with `build` and
+/// `reserved_buffers`, and with `materialize` also pays for writing its
+/// result to disk.
+pub fn best_join_order(tables: &[Table], conditions: &[JoinCondition], memory_size: u32, index_fanout: u32, build: BuildSide, reserved_buffers: u32, materialize: bool) -> Result<JoinOrder, JoinerError> {
+    let mut names: Vec<&str> = Vec::new();
+    for condition in conditions {
+        for name in [&condition.left.0, &condition.right.0] {
+            if !names.contains(&name.as_str()) {
+                names.push(name);
+            }
+        }
+    }
+    if names.len() < 2 {
+        return Err(JoinerError::Input(String::from("a join order needs at least two tables")));
+    }
+    if names.len() > MAX_ORDERING_TABLES {
+        return Err(JoinerError::Input(format!(
+            "join orders can be enumerated for at most {MAX_ORDERING_TABLES} tables, got {}", names.len()
+        )));
+    }
+
+    if memory_size < MIN_MEMORY_BLOCKS {
+        return Err(JoinerError::Input(format!(
+            "joins need at least {MIN_MEMORY_BLOCKS} blocks of memory, got {memory_size}"
+        )));
+    }
+
+    let mut relations: Vec<Table> = Vec::new();
+    for name in &names {
+        relations.push(qualified(find_table(tables, name)?));
+    }
+    for JoinCondition { left: (table1, column1), right: (table2, column2), .. } in conditions {
+        find_column(find_table(tables, table1)?, column1)?;
+        find_column(find_table(tables, table2)?, column2)?;
+    }
+
+    let candidates = |join: &ResolvedJoin| join_candidates(join, memory_size, index_fanout, build, reserved_buffers);
+    let mut best: Option<JoinOrder> = None;
+    for order in permutations(relations.len()) {
+        if let Some(plan) = cost_join_order(&relations, &order, conditions, &candidates, materialize) {
+            if best.as_ref().is_none_or(|x| plan.total_cost < x.total_cost) {
+                best = Some(plan);
+            }
+        }
+    }
+
+    match best {
+        Some(x) => Ok(x),
+        None => Err(JoinerError::Input(format!(
+            "the join conditions don't connect all of {}", names.join(", ")
+        ))),
+    }
+}
+
+/// Joins `relations` in `order`, each step by the cheapest of the methods
+/// `candidates` costs, or `None` if a step has no condition to join on.
+fn cost_join_order(relations: &[Table], order: &[usize], conditions: &[JoinCondition], candidates: &impl Fn(&ResolvedJoin) -> Vec<(JoinMethod, Option<u64>)>, materialize: bool) -> Option<JoinOrder> {
+    let first = &relations[order[0]];
+    let mut joined: Vec<&str> = vec![&first.name];
+    let mut current: Table = first.clone();
+    let mut steps: Vec<JoinStep> = Vec::new();
+    let mut total_cost: u64 = 0;
+
+    for &i in &order[1..] {
+        let next = &relations[i];
+        let mut keys: Vec<(&Column, &Column)> = Vec::new();
+        let mut filters: Vec<(&Column, Comparison, &Column)> = Vec::new();
+        let mut wrapped: (Vec<&Column>, Vec<&Column>) = (Vec::new(), Vec::new());
+        for condition in conditions {
+            let (table1, table2) = (&condition.left.0, &condition.right.0);
+            // Oriented so the left side is in the joined relation.
+            let condition = if joined.contains(&table1.as_str()) && *table2 == next.name {
+                condition.clone()
+            } else if joined.contains(&table2.as_str()) && *table1 == next.name {
+                condition.clone().flipped()
+            } else {
+                continue;
+            };
+            let JoinCondition { left: (table1, column1), op, right: (table2, column2), left_function, right_function } = condition;
+            let outer = find_column(&current, &format!("{table1}.{column1}")).ok()?;
+            let inner = find_column(next, &format!("{table2}.{column2}")).ok()?;
+            match op {
+                Comparison::Eq => keys.push((outer, inner)),
+                op => filters.push((outer, op, inner)),
+            }
+            if left_function.is_some() {
+                wrapped.0.push(outer);
+            }
+            if right_function.is_some() {
+                wrapped.1.push(inner);
+            }
+        }
+        if keys.is_empty() && filters.is_empty() {
+            return None;
+        }
+
+        let join = ResolvedJoin { table1: &current, table2: next, keys, filters, wrapped };
+        let (method, mut cost) = cheapest_candidate(&candidates(&join))?;
+        let estimated_rows = estimate_filtered_join_cardinality(&current, next, &join.keys, &join.filters, RANGE_SELECTIVITY, JoinType::Inner);
+        if materialize {
+            cost += output_blocks(&current, next, estimated_rows);
+        }
+        steps.push(JoinStep { table: next.name.clone(), method, cost, estimated_rows });
+        total_cost += cost;
+        joined.push(&next.name);
+        current = intermediate_result(&current, next, estimated_rows);
+    }
+
+    Some(JoinOrder { first: first.name.clone(), steps, total_cost })
+}
+
+/// Renames every column to `<table>.<column>` so joined results keep them apart.
+fn qualified(table: &Table) -> Table {
+    let mut table = table.clone();
+    for column in table.columns.iter_mut() {
+        column.name = format!("{}.{}", table.name, column.name);
+    }
+    if let Some(sorted) = table.sorted_column.as_mut() {
+        sorted.name = format!("{}.{}", table.name, sorted.name);
+    }
+
+    table
+}
+
+/*
+ * Intermediate results are pipelined into the next join without indexes and
+ * without a known sort order. Their tuples are as wide as both inputs
+ * together, taking `output_blocks` blocks.
+ */
+fn intermediate_result(left: &Table, right: &Table, rows: u64) -> Table {
+    let nr = rows.min(u32::MAX as u64) as u32;
+    let br = output_blocks(left, right, nr as u64).min(u32::MAX as u64) as u32;
+
+    let columns: Vec<Column> = left
+        .columns
+        .iter()
+        .chain(&right.columns)
+        .map(|column| Column {
+            name: column.name.clone(),
+            indexed: false,
+            clustered: false,
+            total_values: column.total_values.min(nr),
+            data_type: column.data_type.clone(),
+            index_group: None,
+            histogram: Vec::new(),
+            index_type: None,
+            null_count: 0,
+            index_coverage: None,
+        })
+        .collect();
+
+    Table {
+        name: format!("({} X {})", left.name, right.name),
+        columns,
+        sorted_column: None,
+        nr,
+        br,
+        tuple_size: None,
+        block_size: None,
+        shards: 1,
+        memory_hint: None,
+        is_intermediate: true,
+    }
+}
+
+fn permutations(n: usize) -> Vec<Vec<usize>> {
+    if n == 0 {
+        return vec![Vec::new()];
+    }
+    let mut result: Vec<Vec<usize>> = Vec::new();
+    for mut order in permutations(n - 1) {
+        for i in 0..n {
+            order.insert(i, n - 1);
+            result.push(order.clone());
+            order.remove(i);
+        }
+    }
+
+    result
+}
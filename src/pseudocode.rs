@@ -0,0 +1,77 @@
+//! Textbook pseudocode of every join method, as the CLI prints it with
+//! `--pseudocode`. R is the left (or outer, or build) relation and S the
+//! right one, with M blocks of memory.
+
+use crate::JoinMethod;
+
+const BLOCK_NESTED: &str = "\
+Block Nested Join(R, S):
+    for each chunk of R filling the memory but its reserved buffers:
+        for each block bs of S:
+            for each tuple r in the chunk, s in bs:
+                if r and s satisfy the condition: output (r, s)";
+
+const NESTED_LOOP: &str = "\
+Nested Loop Join(R, S):
+    for each tuple r in R:
+        for each tuple s in S:
+            if r and s satisfy the condition: output (r, s)";
+
+const INDEXED: &str = "\
+Indexed Join(R, S):
+    let S be the side with an index on its join column, or the cheaper one to probe
+    for each tuple r in R:
+        look up r's key in the index on S
+        for each tuple s the index points to: output (r, s)";
+
+const INDEXED_NESTED_LOOP: &str = "\
+Indexed Nested Loop Join(R, S):
+    for each tuple r in R:
+        look up r's key in the index on S's join column
+        for each tuple s the index points to: output (r, s)";
+
+const MERGE: &str = "\
+Merge Join(R, S):
+    sort R and S on the join column, unless already sorted
+    r, s = first tuples of R and S
+    while neither input is exhausted:
+        if r.key < s.key: advance r
+        else if r.key > s.key: advance s
+        else:
+            read every tuple of R and of S with that key
+            output every pair of them
+            advance r and s past that key";
+
+const HASH: &str = "\
+Hash Join(R, S):
+    partition R into nh buckets by h(key), writing each to disk
+    partition S into the same nh buckets by h(key), writing each to disk
+    for each bucket i:
+        build an in-memory hash table on R_i
+        for each tuple s in S_i:
+            probe the hash table with s.key
+            for each matching r: output (r, s)";
+
+const HYBRID_HASH: &str = "\
+Hybrid Hash Join(R, S):
+    partition R into nh buckets by h(key), keeping bucket 0 in a hash table
+    and writing the others to disk
+    for each tuple s in S:
+        if h(s.key) is bucket 0: probe the in-memory table and output the matches
+        else: write s to its bucket on disk
+    for each bucket i > 0:
+        build an in-memory hash table on R_i
+        probe it with every tuple of S_i and output the matches";
+
+/// The pseudocode of `method`, starting with a `<method>(R, S):` header line.
+pub fn pseudocode(method: JoinMethod) -> &'static str {
+    match method {
+        JoinMethod::BlockNested => BLOCK_NESTED,
+        JoinMethod::NestedLoop => NESTED_LOOP,
+        JoinMethod::Indexed => INDEXED,
+        JoinMethod::IndexedNestedLoop => INDEXED_NESTED_LOOP,
+        JoinMethod::Merge => MERGE,
+        JoinMethod::Hash => HASH,
+        JoinMethod::HybridHash => HYBRID_HASH,
+    }
+}
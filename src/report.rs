@@ -0,0 +1,247 @@
+//! Everything estimated about one join, gathered for callers that render or
+//! serialize it themselves, such as the CLI's text, JSON and CSV output.
+
+use serde::Serialize;
+use std::cmp;
+
+use crate::{
+    add_cpu_costs, anti_join_cardinality, anti_join_cost, assume_block_nested_no_fit,
+    choose_candidate, clamp_memory_size, default_index_fanout, discount_sequential_scans,
+    estimate_filtered_join_cardinality, estimated_time_ms, explain_block_nested_no_fit,
+    explain_candidates, join_candidates, method_caveats, method_cost, output_blocks,
+    resolve_aliases, resolve_join, semi_join_cost, BuildSide, CostModel, JoinCondition, JoinMethod,
+    JoinType, JoinerError, Predicate, ResolvedJoin, Table, BLOCK_SIZE, DEFAULT_CPU_WEIGHT,
+    DEFAULT_IO_LATENCY_MS, DEFAULT_MEMORY_SIZE, DEFAULT_RESERVED_BUFFERS, RANGE_SELECTIVITY,
+};
+
+/// How `join_report` estimates a join.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReportOptions {
+    pub memory_size: u32,
+    /// Memory in bytes, which replaces `memory_size` with as many blocks of
+    /// the joined tables as fit in it, using the larger of their block sizes.
+    pub memory_bytes: Option<u64>,
+    pub index_fanout: u32,
+    pub join_type: JoinType,
+    /// Describe the formula behind every candidate's cost.
+    pub explain: bool,
+    /// Only cost this method instead of picking the cheapest.
+    pub method: Option<JoinMethod>,
+    /// Add the cost of writing the result to disk to every method.
+    pub materialize: bool,
+    /// Also cost a bloom-filter semi-join keeping the rows of the first table.
+    pub semi: bool,
+    /// Also cost an anti-join keeping the rows of the first table without a
+    /// match in the second, as for NOT IN or NOT EXISTS.
+    pub anti: bool,
+    /// Which relation hash joins build from.
+    pub build: BuildSide,
+    /// Milliseconds to transfer one block, for the estimated time.
+    pub io_latency_ms: f64,
+    /// Memory blocks block nested loop can't use for the outer relation.
+    pub reserved_buffers: u32,
+    /// Cost block nested loop as if the outer relation never fit in memory,
+    /// however large the memory of the join is.
+    pub assume_no_fit: bool,
+    /// Fraction taken off the cost of methods that only read sequentially.
+    pub sequential_discount: f64,
+    /// `(alias, table)` pairs the predicate may name tables by.
+    pub aliases: Vec<(String, String)>,
+    /// Whether costs also count tuple comparisons.
+    pub cost_model: CostModel,
+    /// Blocks one comparison is worth under `CostModel::IoCpu`.
+    pub cpu_weight: f64,
+    /// Fraction of the row pairs a range condition such as `<` keeps.
+    pub range_selectivity: f64,
+}
+
+impl Default for ReportOptions {
+    fn default() -> Self {
+        ReportOptions {
+            memory_size: DEFAULT_MEMORY_SIZE,
+            memory_bytes: None,
+            index_fanout: default_index_fanout(BLOCK_SIZE),
+            join_type: JoinType::default(),
+            explain: false,
+            method: None,
+            materialize: false,
+            semi: false,
+            anti: false,
+            build: BuildSide::default(),
+            io_latency_ms: DEFAULT_IO_LATENCY_MS,
+            reserved_buffers: DEFAULT_RESERVED_BUFFERS,
+            assume_no_fit: false,
+            sequential_discount: 0.0,
+            aliases: Vec::new(),
+            cost_model: CostModel::default(),
+            cpu_weight: DEFAULT_CPU_WEIGHT,
+            range_selectivity: RANGE_SELECTIVITY,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReportColumn {
+    pub table: String,
+    pub column: String,
+    /// The function the column is wrapped in, such as `UPPER`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub function: Option<String>,
+}
+
+/// One condition of the predicate as it was entered, before aliases are resolved.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReportCondition {
+    pub left: ReportColumn,
+    pub op: String,
+    pub right: ReportColumn,
+}
+
+/// A method's cost, `None` where it isn't applicable.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReportCandidate {
+    pub method: JoinMethod,
+    pub cost: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<String>,
+}
+
+/// The estimate of one join: every candidate, the winner, and the caveats
+/// that apply to it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct JoinReport {
+    pub best_method: JoinMethod,
+    pub best_cost: u64,
+    pub estimated_time_ms: f64,
+    pub estimated_rows: u64,
+    pub memory_size: u32,
+    pub join_type: String,
+    pub predicate: Vec<ReportCondition>,
+    /// Every method in `JoinMethod::ALL` order, or only the forced one.
+    pub candidates: Vec<ReportCandidate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub semi_join_cost: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub anti_join_cost: Option<u64>,
+    /// Rows of the first table the anti-join keeps.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub anti_join_rows: Option<u64>,
+    /// Assumptions behind the best method's cost that the statistics strain.
+    pub warnings: Vec<String>,
+}
+
+impl JoinReport {
+    /// The candidates as `(method, cost)` pairs, the shape the estimator's
+    /// helpers such as `top_candidates` take.
+    pub fn costs(&self) -> Vec<(JoinMethod, Option<u64>)> {
+        self.candidates.iter().map(|x| (x.method, x.cost)).collect()
+    }
+}
+
+/// Estimates the join `predicate` describes between two of `tables`.
+pub fn join_report(tables: &[Table], predicate: &Predicate, options: &ReportOptions) -> Result<JoinReport, JoinerError> {
+    let conditions = resolve_aliases(tables, &predicate.conditions, &options.aliases)?;
+    let join = resolve_join(tables, &conditions)?;
+    let (options, memory_warning) = match options.memory_bytes {
+        Some(bytes) => {
+            let table = cmp::max_by_key(join.table1, join.table2, |x| x.block_bytes());
+            let (memory_size, warning) = clamp_memory_size(table.memory_blocks(bytes)?);
+            (&ReportOptions { memory_size, ..options.clone() }, warning)
+        }
+        None => (options, None),
+    };
+    let ReportOptions { memory_size, index_fanout, join_type, explain, method, semi, anti, build, reserved_buffers, .. } = *options;
+    // The memory of a join with its own block size may be smaller than the one the caller checked.
+    if memory_size <= reserved_buffers {
+        return Err(JoinerError::Usage(format!(
+            "--reserved-buffers ({reserved_buffers}) must be less than the memory size ({memory_size} blocks)"
+        )));
+    }
+    let estimated_rows = estimate_filtered_join_cardinality(join.table1, join.table2, &join.keys, &join.filters, options.range_selectivity, join_type);
+
+    let costs = costed_candidates(&join, estimated_rows, options)?;
+    let explanations: Vec<Option<String>> = match explain {
+        true => explain_candidates(&join, memory_size, index_fanout, build, reserved_buffers)
+            .into_iter()
+            .filter(|(x, _)| method.is_none_or(|forced| forced == *x))
+            .map(|(x, explanation)| match x == JoinMethod::BlockNested && options.assume_no_fit {
+                true => explanation.map(|_| explain_block_nested_no_fit(&join)),
+                false => explanation,
+            })
+            .collect(),
+        false => vec![None; costs.len()],
+    };
+    let (best_method, best_cost) = choose_candidate(&join, &costs, memory_size)?;
+    // The second table builds the filter, the first one is probed and keeps its matching rows.
+    let semi_join_cost = match (semi, join.keys.first()) {
+        (false, _) => None,
+        (true, Some((column1, column2))) => semi_join_cost(join.table2, column2, join.table1, column1, memory_size),
+        (true, None) => return Err(JoinerError::Input(String::from("--semi needs an equality condition"))),
+    };
+    // Like the semi-join, but keeping the rows of the first table the filter and the hash join rule out.
+    let (anti_join_cost, anti_join_rows) = match (anti, join.keys.first()) {
+        (false, _) => (None, None),
+        (true, Some((column1, column2))) => (
+            anti_join_cost(join.table2, column2, join.table1, column1, memory_size),
+            Some(anti_join_cardinality(column2, join.table1, column1)),
+        ),
+        (true, None) => return Err(JoinerError::Input(String::from("--anti needs an equality condition"))),
+    };
+
+    Ok(JoinReport {
+        best_method,
+        best_cost,
+        estimated_time_ms: estimated_time_ms(best_cost, options.io_latency_ms),
+        estimated_rows,
+        memory_size,
+        join_type: join_type.to_string(),
+        predicate: predicate
+            .conditions
+            .iter()
+            .map(|JoinCondition { left: (t1, c1), op, right: (t2, c2), left_function, right_function }| ReportCondition {
+                left: ReportColumn { table: t1.clone(), column: c1.clone(), function: left_function.clone() },
+                op: op.to_string(),
+                right: ReportColumn { table: t2.clone(), column: c2.clone(), function: right_function.clone() },
+            })
+            .collect(),
+        candidates: costs
+            .into_iter()
+            .zip(explanations)
+            .map(|((method, cost), explanation)| ReportCandidate { method, cost, explanation })
+            .collect(),
+        semi_join_cost,
+        anti_join_cost,
+        anti_join_rows,
+        warnings: memory_warning.into_iter().chain(method_caveats(&join, best_method, memory_size, build)).collect(),
+    })
+}
+
+/// The cost of every candidate method, or only the forced one, with block
+/// nested loop's outer assumed not to fit when asked, the sequential
+/// discount taken off, comparisons added under the io+cpu cost model, and
+/// including writing the result when it's materialized.
+fn costed_candidates(join: &ResolvedJoin, cardinality: u64, options: &ReportOptions) -> Result<Vec<(JoinMethod, Option<u64>)>, JoinerError> {
+    let ReportOptions {
+        memory_size, index_fanout, method, materialize, build, reserved_buffers, assume_no_fit, sequential_discount,
+        cost_model, cpu_weight, ..
+    } = *options;
+    let mut candidates = match method {
+        Some(x) => vec![(x, Some(method_cost(join, x, memory_size, index_fanout, build, reserved_buffers)?))],
+        None => join_candidates(join, memory_size, index_fanout, build, reserved_buffers),
+    };
+    if assume_no_fit {
+        assume_block_nested_no_fit(&mut candidates, join);
+    }
+    discount_sequential_scans(&mut candidates, sequential_discount);
+    if cost_model == CostModel::IoCpu {
+        add_cpu_costs(&mut candidates, join, cpu_weight);
+    }
+    if materialize {
+        let written = output_blocks(join.table1, join.table2, cardinality);
+        for cost in candidates.iter_mut().filter_map(|(_, cost)| cost.as_mut()) {
+            *cost += written;
+        }
+    }
+
+    Ok(candidates)
+}
@@ -0,0 +1,809 @@
+use serde::{de, Deserialize, Deserializer};
+use serde_json::Value;
+use std::{collections::BTreeMap, fmt, fs, io::{self, Read}, path::Path};
+
+use crate::{
+    gzip, memory_blocks_from_bytes, yaml, ColumnRef, Comparison, JoinCondition, JoinerError,
+    SelectivityMode, BLOCK_SIZE, MIN_MEMORY_BLOCKS,
+};
+
+/// How an index finds the entries for a key.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum IndexType {
+    /// A B+-tree, walked from the root down to a leaf.
+    #[default]
+    BTree,
+    /// A hash index, going straight to the key's bucket.
+    Hash,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Column {
+    pub name: String,
+    #[serde(default)]
+    pub indexed: bool,
+    /// Whether the index on this column also determines the table's physical order.
+    #[serde(default)]
+    pub clustered: bool,
+    #[serde(deserialize_with = "deserialize_count")]
+    pub total_values: u32,
+    /// SQL type of the column such as "int" or "varchar(40)", if known.
+    #[serde(default)]
+    pub data_type: Option<String>,
+    /// Name of the composite index this column is part of. The columns of a
+    /// group make up its key in the order they're listed in the table.
+    #[serde(default)]
+    pub index_group: Option<String>,
+    /// Row counts of an equi-width histogram over the column's values, empty when there's none.
+    #[serde(default)]
+    pub histogram: Vec<u32>,
+    /// Kind of index on the column, a B+-tree unless given.
+    #[serde(default)]
+    pub index_type: Option<IndexType>,
+    /// Rows (of one shard) where the column is NULL.
+    #[serde(default)]
+    pub null_count: u32,
+    /// Fraction of the rows a partial index on the column covers, such as
+    /// one built `WHERE status = 'active'`; the whole table unless given.
+    #[serde(default)]
+    pub index_coverage: Option<f64>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(try_from = "TableMetadata")]
+pub struct Table {
+    pub name: String,
+    pub columns: Vec<Column>,
+    /// The column the table is stored sorted on, `None` for an unsorted heap.
+    pub sorted_column: Option<Column>,
+    pub nr: u32,
+    pub br: u32,
+    /// Size of one tuple in bytes, if known.
+    pub tuple_size: Option<u32>,
+    /// Size of one block in bytes, if it differs from `BLOCK_SIZE`, given as
+    /// `blockSize` or `blockBytes`. Joining tables of different block sizes
+    /// adds their block counts as if they were the same size, which
+    /// `method_caveats` warns about.
+    pub block_size: Option<u32>,
+    /// Number of horizontal shards; `nr` and `br` describe a single shard.
+    pub shards: u32,
+    /// Memory in blocks a join may use while this table dominates its cost,
+    /// overriding the global memory size; see `TableStats::memory_hint`.
+    pub memory_hint: Option<u32>,
+    /// Whether the table is the pipelined result of another join, which has
+    /// no indexes and no sort order whatever its columns say.
+    pub is_intermediate: bool,
+}
+
+impl Table {
+    /// Rows across all shards.
+    pub fn total_rows(&self) -> u64 {
+        self.nr as u64 * self.shards as u64
+    }
+
+    /// Whether lookups on `column` alone can use an index; see `TableStats::has_index_on`.
+    pub fn has_index_on(&self, column: &Column) -> bool {
+        TableStats::has_index_on(self, column)
+    }
+
+    /// Whether the table is stored sorted on `column`; see `TableStats::is_sorted_on`.
+    pub fn is_sorted_on(&self, column: &Column) -> bool {
+        TableStats::is_sorted_on(self, column)
+    }
+
+    /// Size of one of the table's blocks in bytes.
+    pub fn block_bytes(&self) -> u32 {
+        self.block_size.unwrap_or(BLOCK_SIZE)
+    }
+
+    /// Whole blocks of this table that fit in `bytes` bytes of memory.
+    pub fn memory_blocks(&self, bytes: u64) -> Result<u32, JoinerError> {
+        memory_blocks_from_bytes(bytes, self.block_bytes())
+    }
+}
+
+/// The statistics of a table the cost functions read. `Table` implements it
+/// from the metadata; other sources, such as a live catalog, can implement it
+/// without building a `Table`. Like `Table`, `nr` and `br` describe one shard.
+pub trait TableStats {
+    fn nr(&self) -> u32;
+    fn br(&self) -> u32;
+    fn columns(&self) -> &[Column];
+    fn sorted_column(&self) -> Option<&Column>;
+
+    /// Whether lookups on `column` alone can use an index: it's indexed on
+    /// its own, or it's the first column of a composite index.
+    fn has_index_on(&self, column: &Column) -> bool {
+        if self.is_intermediate() {
+            return false;
+        }
+        if column.indexed {
+            return true;
+        }
+        let group = match &column.index_group {
+            Some(x) => x,
+            None => return false,
+        };
+        self.columns()
+            .iter()
+            .find(|x| x.index_group.as_ref() == Some(group))
+            .is_some_and(|first| first.name == column.name)
+    }
+
+    /// Whether the table is stored sorted on `column`; an unsorted table or
+    /// an intermediate result isn't sorted on any.
+    fn is_sorted_on(&self, column: &Column) -> bool {
+        !self.is_intermediate() && self.sorted_column().is_some_and(|x| x.name == column.name)
+    }
+
+    /// Whether the table is the result of another join rather than stored;
+    /// see `Table::is_intermediate`.
+    fn is_intermediate(&self) -> bool {
+        false
+    }
+
+    /// Memory in blocks to use instead of the global memory size when this
+    /// table dominates a join's cost. Only hash and hybrid hash join, whose
+    /// cost turns on whether the build relation fits, consult it, and only
+    /// for the build side; every other method uses the global memory.
+    fn memory_hint(&self) -> Option<u32> {
+        None
+    }
+}
+
+impl<T: TableStats + ?Sized> TableStats for &T {
+    fn nr(&self) -> u32 {
+        (**self).nr()
+    }
+
+    fn br(&self) -> u32 {
+        (**self).br()
+    }
+
+    fn columns(&self) -> &[Column] {
+        (**self).columns()
+    }
+
+    fn sorted_column(&self) -> Option<&Column> {
+        (**self).sorted_column()
+    }
+
+    fn memory_hint(&self) -> Option<u32> {
+        (**self).memory_hint()
+    }
+
+    fn is_intermediate(&self) -> bool {
+        (**self).is_intermediate()
+    }
+}
+
+impl TableStats for Table {
+    fn nr(&self) -> u32 {
+        self.nr
+    }
+
+    fn br(&self) -> u32 {
+        self.br
+    }
+
+    fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    fn sorted_column(&self) -> Option<&Column> {
+        self.sorted_column.as_ref()
+    }
+
+    fn memory_hint(&self) -> Option<u32> {
+        self.memory_hint
+    }
+
+    fn is_intermediate(&self) -> bool {
+        self.is_intermediate
+    }
+}
+
+/// A table as written in the metadata file, where `br` may be left out in
+/// favour of `tupleSize` (and optionally `blockSize`).
+#[derive(Deserialize)]
+struct TableMetadata {
+    name: String,
+    #[serde(flatten)]
+    body: TableBody,
+}
+
+/// Everything but the name of a table, for metadata keyed by table name.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TableBody {
+    columns: Vec<Column>,
+    /// Metadata without a sorted column describes an unsorted heap.
+    #[serde(default)]
+    sorted_column: Option<Column>,
+    #[serde(deserialize_with = "deserialize_count")]
+    nr: u32,
+    #[serde(default, deserialize_with = "deserialize_optional_count")]
+    br: Option<u32>,
+    tuple_size: Option<u32>,
+    block_size: Option<u32>,
+    /// Another name for `blockSize`; an alias isn't seen through the flattened body.
+    block_bytes: Option<u32>,
+    shards: Option<u32>,
+    memory_hint: Option<u32>,
+    #[serde(default)]
+    is_intermediate: bool,
+}
+
+impl TryFrom<TableMetadata> for Table {
+    type Error = String;
+
+    fn try_from(metadata: TableMetadata) -> Result<Self, Self::Error> {
+        let TableMetadata { name, body } = metadata;
+        let block_size = body.block_size.or(body.block_bytes);
+        let br = match (body.br, body.tuple_size) {
+            (Some(br), _) => br,
+            (None, Some(tuple_size)) => {
+                let block_size = block_size.unwrap_or(BLOCK_SIZE);
+                if block_size == 0 {
+                    return Err(format!("table {name} has a block size of zero"));
+                }
+                derive_block_count(body.nr, tuple_size, block_size)
+            }
+            (None, None) => return Err(format!(
+                "table {name} needs either br or tupleSize to know its block count"
+            )),
+        };
+
+        let shards = body.shards.unwrap_or(1);
+        if shards == 0 {
+            return Err(format!("table {name} has zero shards"));
+        }
+
+        if let Some(hint) = body.memory_hint.filter(|x| *x < MIN_MEMORY_BLOCKS) {
+            return Err(format!(
+                "table {name} has a memory hint of {hint} blocks, it needs at least {MIN_MEMORY_BLOCKS}"
+            ));
+        }
+
+        Ok(Table {
+            name,
+            columns: body.columns,
+            sorted_column: body.sorted_column,
+            nr: body.nr,
+            br,
+            tuple_size: body.tuple_size,
+            block_size,
+            shards,
+            memory_hint: body.memory_hint,
+            is_intermediate: body.is_intermediate,
+        })
+    }
+}
+
+/// A count as written in the metadata: a number, or a string such as
+/// `"1.5e6"` or `"1_500_000"` that's easier to read than all the digits.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Count {
+    Number(f64),
+    Text(String),
+}
+
+impl Count {
+    /// The count rounded to the nearest whole number.
+    fn value(self) -> Result<u32, String> {
+        let (number, text) = match self {
+            Count::Number(x) => (Some(x), x.to_string()),
+            Count::Text(x) => (x.trim().replace('_', "").parse::<f64>().ok(), x),
+        };
+        match number.map(f64::round) {
+            Some(x) if (0.0..=u32::MAX as f64).contains(&x) => Ok(x as u32),
+            _ => Err(format!("invalid count '{text}', expected a whole number such as 1500000, 1_500_000 or 1.5e6")),
+        }
+    }
+}
+
+fn deserialize_count<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    Count::deserialize(deserializer)?.value().map_err(de::Error::custom)
+}
+
+fn deserialize_optional_count<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<u32>, D::Error> {
+    match Option::<Count>::deserialize(deserializer)? {
+        Some(x) => x.value().map(Some).map_err(de::Error::custom),
+        None => Ok(None),
+    }
+}
+
+/// Blocks needed to store `nr` tuples of `tuple_size` bytes: ceil(nr * tuple_size / block_size).
+pub fn derive_block_count(nr: u32, tuple_size: u32, block_size: u32) -> u32 {
+    (nr as u64 * tuple_size as u64).div_ceil(block_size as u64) as u32
+}
+
+/// Path that makes `load_metadata` read the metadata from stdin instead.
+pub const STDIN_PATH: &str = "-";
+
+/// Reads and parses the metadata at `path`, as YAML when it's named `*.yaml`
+/// or `*.yml` and as JSON otherwise; I/O and parse errors name the file.
+/// `mode` says what the columns' `total_values` state.
+pub fn load_metadata<P: AsRef<Path>>(path: P, mode: SelectivityMode) -> Result<Vec<Table>, JoinerError> {
+    let tables = read_tables_from_file(path, mode)?;
+    validate_tables(&tables)?;
+
+    Ok(tables)
+}
+
+/// Like `load_metadata`, but leaves checking the tables to the caller.
+/// Gzip-compressed metadata, named `*.gz` or starting with the gzip magic
+/// number, is decompressed first.
+pub fn read_tables_from_file<P: AsRef<Path>>(path: P, mode: SelectivityMode) -> Result<Vec<Table>, JoinerError> {
+    let (content, source) = if path.as_ref() == Path::new(STDIN_PATH) {
+        let mut bytes: Vec<u8> = Vec::new();
+        (io::stdin().read_to_end(&mut bytes).map(|_| bytes), Path::new("stdin"))
+    } else {
+        (fs::read(&path), path.as_ref())
+    };
+    let gzipped = source.extension().is_some_and(|x| x == "gz");
+    // The format is named by the extension under any .gz.
+    let name = match gzipped {
+        true => source.file_stem().map(Path::new).unwrap_or(source),
+        false => source,
+    };
+    let yaml = name.extension().is_some_and(|x| x == "yaml" || x == "yml");
+
+    content
+        .and_then(|bytes| metadata_text(bytes, gzipped))
+        .map_err(JoinerError::from)
+        .and_then(|content| match (yaml, mode) {
+            (false, SelectivityMode::Distinct) => deserialize_tables(&content),
+            (true, SelectivityMode::Distinct) => tables_from_value(yaml_value(&content, source)?),
+            (_, SelectivityMode::Fraction) => {
+                let mut value = match yaml {
+                    true => yaml_value(&content, source)?,
+                    false => serde_json::from_str(&content)?,
+                };
+                fractions_to_distinct_counts(&mut value)?;
+                tables_from_value(value)
+            }
+        })
+        .map_err(|err| err.with_path(source))
+}
+
+/// The JSON value YAML metadata read from `source` denotes.
+fn yaml_value(content: &str, source: &Path) -> Result<Value, JoinerError> {
+    yaml::parse(content).map_err(|(line, msg)| {
+        JoinerError::Input(format!("invalid YAML in {} at line {line}: {msg}", source.display()))
+    })
+}
+
+/*
+ * In fraction mode a column's total_values is the selectivity of an equality
+ * on it, 1 / V, instead of its distinct count V. Every one is rewritten to
+ * round(1 / fraction) up front, so the cardinality and index estimates keep
+ * working with distinct counts.
+ */
+fn fractions_to_distinct_counts(value: &mut Value) -> Result<(), JoinerError> {
+    // Metadata keyed by table name leaves the name out of the table itself.
+    let tables: Vec<(Option<String>, &mut Value)> = match value {
+        Value::Array(x) => x.iter_mut().map(|table| (None, table)).collect(),
+        Value::Object(x) => x.iter_mut().map(|(name, table)| (Some(name.clone()), table)).collect(),
+        _ => return Ok(()),
+    };
+    for (name, table) in tables {
+        let table_name = name.or_else(|| table.get("name").and_then(Value::as_str).map(String::from)).unwrap_or_default();
+        let mut columns: Vec<&mut Value> = Vec::new();
+        if let Value::Object(table) = table {
+            for (key, value) in table.iter_mut() {
+                match (key.as_str(), value) {
+                    ("columns", Value::Array(x)) => columns.extend(x.iter_mut()),
+                    ("sortedColumn", x) => columns.push(x),
+                    _ => {}
+                }
+            }
+        }
+        for column in columns {
+            let name = column.get("name").and_then(Value::as_str).unwrap_or_default().to_string();
+            let total_values = match column.get_mut("total_values") {
+                Some(x) => x,
+                None => continue,
+            };
+            let fraction = match &*total_values {
+                Value::Number(x) => x.as_f64(),
+                Value::String(x) => x.replace('_', "").parse::<f64>().ok(),
+                _ => None,
+            };
+            match fraction {
+                Some(x) if x > 0.0 && x <= 1.0 => *total_values = Value::from((1.0 / x).round().min(u32::MAX as f64) as u32),
+                _ => return Err(JoinerError::Input(format!(
+                    "column {table_name}.{name} has total_values {total_values}, which in fraction mode has to be greater than 0 and at most 1"
+                ))),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The tables of metadata parsed into a JSON value, in either layout.
+fn tables_from_value(value: Value) -> Result<Vec<Table>, JoinerError> {
+    match value.is_object() {
+        true => tables_from_map(serde_json::from_value(value.clone()).map_err(|err| in_faulty_table(err, &value))?),
+        false => serde_json::from_value(value.clone()).map_err(|err| in_faulty_table(err, &value)),
+    }
+}
+
+/// `err` from deserializing the tables of `value`, naming the first table
+/// entry that doesn't deserialize on its own.
+fn in_faulty_table(err: serde_json::Error, value: &Value) -> JoinerError {
+    let table = match value {
+        Value::Array(tables) => tables.iter().enumerate().find_map(|(i, table)| {
+            serde_json::from_value::<Table>(table.clone()).err()?;
+            Some(match table.get("name").and_then(Value::as_str) {
+                Some(name) => format!("the table at index {i} ({name})"),
+                None => format!("the table at index {i}"),
+            })
+        }),
+        Value::Object(tables) => tables.iter().find_map(|(name, table)| {
+            serde_json::from_value::<TableBody>(table.clone()).err()?;
+            Some(format!("table {name}"))
+        }),
+        _ => None,
+    };
+
+    JoinerError::Parse { path: None, source: err, table }
+}
+
+fn metadata_text(bytes: Vec<u8>, gzipped: bool) -> io::Result<String> {
+    let bytes = match gzipped || gzip::is_gzip(&bytes) {
+        true => gzip::decompress(&bytes)?,
+        false => bytes,
+    };
+
+    String::from_utf8(bytes).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+/// Loads the tables of every file in `paths` into one set, rejecting a table
+/// name that's defined by more than one file.
+pub fn load_all<P: AsRef<Path>>(paths: &[P], mode: SelectivityMode) -> Result<Vec<Table>, JoinerError> {
+    let mut tables: Vec<Table> = Vec::new();
+    let mut sources: Vec<&Path> = Vec::new();
+    for path in paths {
+        for table in load_metadata(path, mode)? {
+            if let Some(i) = tables.iter().position(|x| x.name == table.name) {
+                return Err(JoinerError::Input(format!(
+                    "table {} is defined in both {} and {}",
+                    table.name, sources[i].display(), path.as_ref().display()
+                )));
+            }
+            tables.push(table);
+            sources.push(path.as_ref());
+        }
+    }
+
+    Ok(tables)
+}
+
+/// Deserializes table metadata from a JSON string and validates it.
+pub fn parse_tables(content: &str) -> Result<Vec<Table>, JoinerError> {
+    let tables = deserialize_tables(content)?;
+    validate_tables(&tables)?;
+
+    Ok(tables)
+}
+
+/// Deserializes table metadata from a JSON string without validating it.
+///
+/// The metadata is either an array of tables or an object mapping each table
+/// name to the rest of its fields; the latter yields tables sorted by name.
+pub fn deserialize_tables(content: &str) -> Result<Vec<Table>, JoinerError> {
+    let tables: Vec<Table> = match serde_json::from_str(content) {
+        Ok(x) => x,
+        Err(array_err) => match serde_json::from_str::<BTreeMap<String, TableBody>>(content) {
+            Ok(map) => tables_from_map(map)?,
+            // Report the error for whichever layout the content looks like, in
+            // the table at fault when the JSON itself is well-formed.
+            Err(err) => {
+                let err = if content.trim_start().starts_with('{') { err } else { array_err };
+                return Err(match serde_json::from_str::<Value>(content) {
+                    Ok(value) => in_faulty_table(err, &value),
+                    Err(_) => err.into(),
+                });
+            }
+        },
+    };
+
+    Ok(tables)
+}
+
+/// The tables of metadata written as an object keyed by table name, sorted by name.
+fn tables_from_map(map: BTreeMap<String, TableBody>) -> Result<Vec<Table>, JoinerError> {
+    let mut tables: Vec<Table> = Vec::new();
+    for (name, body) in map {
+        tables.push(Table::try_from(TableMetadata { name, body }).map_err(JoinerError::Input)?);
+    }
+
+    Ok(tables)
+}
+
+/// One problem with the metadata found by `validation_issues`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationIssue {
+    EmptyTableName,
+    DuplicateTable(String),
+    DuplicateColumn { table: String, column: String },
+    SortedColumnMissing { table: String, column: String },
+    TooManyDistinctValues { table: String, column: String, total_values: u32, rows: u64 },
+    NoBlocks(String),
+    IndexCoverageOutOfRange { table: String, column: String, coverage: f64 },
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationIssue::EmptyTableName => write!(f, "a table has an empty name"),
+            ValidationIssue::DuplicateTable(table) => write!(f, "duplicate table {table}"),
+            ValidationIssue::DuplicateColumn { table, column } => {
+                write!(f, "duplicate column {column} in table {table}")
+            }
+            ValidationIssue::SortedColumnMissing { table, column } => {
+                write!(f, "table {table} is sorted on {column}, which is not one of its columns")
+            }
+            ValidationIssue::TooManyDistinctValues { table, column, total_values, rows } => write!(
+                f, "column {table}.{column} has {total_values} distinct values but the table only has {rows} rows"
+            ),
+            ValidationIssue::NoBlocks(table) => write!(f, "table {table} has no blocks (br = 0)"),
+            ValidationIssue::IndexCoverageOutOfRange { table, column, coverage } => write!(
+                f, "column {table}.{column} has index_coverage {coverage}, which is not above 0 and at most 1"
+            ),
+        }
+    }
+}
+
+/// Every consistency problem in `tables`, in the order the tables are listed.
+pub fn validation_issues(tables: &[Table]) -> Vec<ValidationIssue> {
+    let mut issues: Vec<ValidationIssue> = Vec::new();
+    for (i, table) in tables.iter().enumerate() {
+        if table.name.trim().is_empty() {
+            issues.push(ValidationIssue::EmptyTableName);
+        } else if tables[..i].iter().any(|other| other.name == table.name) {
+            issues.push(ValidationIssue::DuplicateTable(table.name.clone()));
+        }
+        for (j, column) in table.columns.iter().enumerate() {
+            if table.columns[..j].iter().any(|other| other.name == column.name) {
+                issues.push(ValidationIssue::DuplicateColumn {
+                    table: table.name.clone(),
+                    column: column.name.clone(),
+                });
+            }
+        }
+        if let Some(sorted) = table.sorted_column.as_ref().filter(|x| !table.columns.iter().any(|column| column.name == x.name)) {
+            issues.push(ValidationIssue::SortedColumnMissing {
+                table: table.name.clone(),
+                column: sorted.name.clone(),
+            });
+        }
+        for column in &table.columns {
+            if column.total_values as u64 > table.total_rows() {
+                issues.push(ValidationIssue::TooManyDistinctValues {
+                    table: table.name.clone(),
+                    column: column.name.clone(),
+                    total_values: column.total_values,
+                    rows: table.total_rows(),
+                });
+            }
+            if let Some(coverage) = column.index_coverage.filter(|x| !(*x > 0.0 && *x <= 1.0)) {
+                issues.push(ValidationIssue::IndexCoverageOutOfRange {
+                    table: table.name.clone(),
+                    column: column.name.clone(),
+                    coverage,
+                });
+            }
+        }
+        if table.br == 0 {
+            issues.push(ValidationIssue::NoBlocks(table.name.clone()));
+        }
+    }
+
+    issues
+}
+
+/// Checks the invariants the cost model relies on but the JSON shape can't
+/// express, starting with there being any tables at all. Distinct counts are
+/// left to `check_distinct_counts`.
+pub fn validate_tables(tables: &[Table]) -> Result<(), JoinerError> {
+    if tables.is_empty() {
+        return Err(JoinerError::Input(String::from("no tables in metadata")));
+    }
+    for issue in validation_issues(tables) {
+        if matches!(issue, ValidationIssue::DuplicateColumn { .. } | ValidationIssue::SortedColumnMissing { .. }) {
+            return Err(JoinerError::Input(issue.to_string()));
+        }
+    }
+
+    Ok(())
+}
+
+/// A column can't have more distinct values than its table has rows. In
+/// `strict` mode such a column is an error; otherwise its `total_values` is
+/// clamped to the row count of all shards and a warning describing the change
+/// is returned.
+pub fn check_distinct_counts(tables: &mut [Table], strict: bool) -> Result<Vec<String>, JoinerError> {
+    let mut warnings: Vec<String> = Vec::new();
+    for table in tables.iter_mut() {
+        let rows = table.total_rows().min(u32::MAX as u64) as u32;
+        for column in table.columns.iter_mut() {
+            if column.total_values <= rows {
+                continue;
+            }
+            let message = format!(
+                "column {}.{} has {} distinct values but the table only has {} rows",
+                table.name, column.name, column.total_values, rows
+            );
+            if strict {
+                return Err(JoinerError::Input(message));
+            }
+            column.total_values = rows;
+            warnings.push(message);
+        }
+        if let Some(sorted) = table.sorted_column.as_mut() {
+            sorted.total_values = sorted.total_values.min(rows);
+        }
+    }
+
+    Ok(warnings)
+}
+
+/// Describes every table followed by an aligned table of its columns, padded
+/// to the widest entry, with `*` marking the column the table is sorted on.
+pub fn format_schema(tables: &[Table]) -> String {
+    let mut out = String::new();
+    for table in tables {
+        let shards = match table.shards {
+            1 => String::new(),
+            x => format!(" per shard, {x} shards"),
+        };
+        out += &format!("{} (nr = {}, br = {}{shards})\n", table.name, table.nr, table.br);
+
+        let rows: Vec<[String; 4]> = table
+            .columns
+            .iter()
+            .map(|column| {
+                let indexed = match (column.indexed, column.clustered, &column.index_group) {
+                    (true, true, _) => "clustered",
+                    (true, false, _) if column.index_type == Some(IndexType::Hash) => "hash",
+                    (true, false, _) => "yes",
+                    (false, _, Some(group)) => group,
+                    _ => "no",
+                };
+                let sorted = if table.is_sorted_on(column) { "*" } else { "" };
+                [column.name.clone(), indexed.to_string(), column.total_values.to_string(), sorted.to_string()]
+            })
+            .collect();
+        let header = ["Name", "Indexed", "TotalValues", "Sorted"].map(String::from);
+        let mut widths = header.clone().map(|x| x.len());
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.len());
+            }
+        }
+        for [name, indexed, total_values, sorted] in std::iter::once(&header).chain(&rows) {
+            let line = format!(
+                "{name:<w0$}  {indexed:<w1$}  {total_values:>w2$}  {sorted}",
+                w0 = widths[0], w1 = widths[1], w2 = widths[2]
+            );
+            out += line.trim_end();
+            out += "\n";
+        }
+        out += "\n";
+    }
+
+    out
+}
+
+/// Lowercases every table and column name, for matching predicates case-insensitively.
+pub fn fold_case(tables: &mut [Table]) {
+    for table in tables.iter_mut() {
+        table.name = table.name.to_lowercase();
+        for column in table.columns.iter_mut() {
+            column.name = column.name.to_lowercase();
+        }
+        if let Some(sorted) = table.sorted_column.as_mut() {
+            sorted.name = sorted.name.to_lowercase();
+        }
+    }
+}
+
+/// The tables and column pairs named by a parsed join predicate.
+#[derive(Debug)]
+pub struct ResolvedJoin<'a> {
+    pub table1: &'a Table,
+    pub table2: &'a Table,
+    /// Column pairs compared for equality, usable as a join key.
+    pub keys: Vec<(&'a Column, &'a Column)>,
+    /// Every other comparison, applied as a filter on matching pairs.
+    pub filters: Vec<(&'a Column, Comparison, &'a Column)>,
+    /// Columns of `table1` and of `table2` that a condition wraps in a
+    /// function, so lookups by the function's result can't use their index.
+    pub wrapped: (Vec<&'a Column>, Vec<&'a Column>),
+}
+
+pub fn find_table<'a>(tables: &'a [Table], name: &str) -> Result<&'a Table, JoinerError> {
+    match tables.iter().find(|table| table.name == name) {
+        Some(table) => Ok(table),
+        None => Err(JoinerError::TableNotFound(name.to_string())),
+    }
+}
+
+pub fn find_column<'a>(table: &'a Table, name: &str) -> Result<&'a Column, JoinerError> {
+    match table.columns.iter().find(|column| column.name == name) {
+        Some(column) => Ok(column),
+        None => Err(JoinerError::ColumnNotFound {
+            table: table.name.clone(),
+            column: name.to_string(),
+            available: table.columns.iter().map(|x| x.name.clone()).collect(),
+        }),
+    }
+}
+
+/// Rewrites the table names in `conditions` that are one of `aliases`, given
+/// as `(alias, table)` pairs, to the table they stand for. An alias hides a
+/// table of the same name. With aliases, naming a table that's neither an
+/// alias nor in `tables` is an error up front.
+pub fn resolve_aliases(tables: &[Table], conditions: &[JoinCondition], aliases: &[(String, String)]) -> Result<Vec<JoinCondition>, JoinerError> {
+    for (i, (alias, table)) in aliases.iter().enumerate() {
+        if aliases[..i].iter().any(|(other, _)| other == alias) {
+            return Err(JoinerError::Input(format!("alias {alias} is defined more than once")));
+        }
+        if find_table(tables, table).is_err() {
+            return Err(JoinerError::Input(format!("alias {alias} refers to unknown table {table}")));
+        }
+    }
+    if aliases.is_empty() {
+        return Ok(conditions.to_vec());
+    }
+
+    let resolve = |(table, column): &ColumnRef| match aliases.iter().find(|(alias, _)| alias == table) {
+        Some((_, name)) => Ok((name.clone(), column.clone())),
+        None if find_table(tables, table).is_ok() => Ok((table.clone(), column.clone())),
+        None => Err(JoinerError::Input(format!("unknown table or alias {table}"))),
+    };
+    conditions
+        .iter()
+        .map(|condition| Ok(JoinCondition {
+            left: resolve(&condition.left)?,
+            right: resolve(&condition.right)?,
+            ..condition.clone()
+        }))
+        .collect()
+}
+
+/// Looks up every table and column referenced by `conditions` in `tables`.
+pub fn resolve_join<'a>(tables: &'a [Table], conditions: &[JoinCondition]) -> Result<ResolvedJoin<'a>, JoinerError> {
+    let (table1_name, table2_name) = match conditions.first() {
+        Some(x) => (&x.left.0, &x.right.0),
+        None => return Err(JoinerError::Input(String::from("empty join predicate"))),
+    };
+    // Each side is looked up on its own, so a self-join resolves both to the same table.
+    let table1 = find_table(tables, table1_name)?;
+    let table2 = find_table(tables, table2_name)?;
+
+    let mut keys: Vec<(&Column, &Column)> = Vec::new();
+    let mut filters: Vec<(&Column, Comparison, &Column)> = Vec::new();
+    let mut wrapped: (Vec<&Column>, Vec<&Column>) = (Vec::new(), Vec::new());
+    for condition in conditions {
+        let column1 = find_column(table1, &condition.left.1)?;
+        let column2 = find_column(table2, &condition.right.1)?;
+        match condition.op {
+            Comparison::Eq => keys.push((column1, column2)),
+            op => filters.push((column1, op, column2)),
+        }
+        if condition.left_function.is_some() {
+            wrapped.0.push(column1);
+        }
+        if condition.right_function.is_some() {
+            wrapped.1.push(column2);
+        }
+    }
+
+    Ok(ResolvedJoin { table1, table2, keys, filters, wrapped })
+}
@@ -0,0 +1,38 @@
+use std::{fmt, str::FromStr};
+
+use crate::JoinerError;
+
+/// What a column's `total_values` states in the metadata.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum SelectivityMode {
+    /// The number of distinct values in the column.
+    #[default]
+    Distinct,
+    /// The selectivity of an equality on the column, 1 / distinct values,
+    /// as a fraction in (0, 1].
+    Fraction,
+}
+
+impl FromStr for SelectivityMode {
+    type Err = JoinerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "distinct" => Ok(SelectivityMode::Distinct),
+            "fraction" => Ok(SelectivityMode::Fraction),
+            _ => Err(JoinerError::Input(format!(
+                "unknown selectivity mode '{s}', expected one of: fraction, distinct"
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for SelectivityMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            SelectivityMode::Distinct => "distinct",
+            SelectivityMode::Fraction => "fraction",
+        };
+        f.pad(name)
+    }
+}
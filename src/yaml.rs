@@ -0,0 +1,303 @@
+//! Reads the subset of YAML that hand-written metadata uses: block mappings
+//! and sequences, flow sequences and mappings of scalars, plain and quoted
+//! scalars, and comments. Anchors, tags and multi-line scalars aren't supported.
+
+use serde_json::{Map, Number, Value};
+
+/// A YAML error, as the line it's on (counting from 1) and what's wrong.
+pub type YamlError = (usize, String);
+
+struct Line<'a> {
+    number: usize,
+    indent: usize,
+    content: &'a str,
+}
+
+/// Parses a YAML document into the JSON value it denotes.
+pub fn parse(input: &str) -> Result<Value, YamlError> {
+    let mut lines: Vec<Line> = Vec::new();
+    for (i, raw) in input.lines().enumerate() {
+        let content = strip_comment(raw).trim_end();
+        let trimmed = content.trim_start();
+        if trimmed.is_empty() || trimmed == "---" {
+            continue;
+        }
+        if content.starts_with('\t') {
+            return Err((i + 1, String::from("tabs can't be used for indentation")));
+        }
+        lines.push(Line { number: i + 1, indent: content.len() - trimmed.len(), content: trimmed });
+    }
+    if lines.is_empty() {
+        return Ok(Value::Null);
+    }
+
+    let mut pos = 0;
+    let indent = lines[0].indent;
+    let value = parse_block(&mut lines, &mut pos, indent)?;
+    match lines.get(pos) {
+        Some(line) => Err((line.number, String::from("unexpected indentation"))),
+        None => Ok(value),
+    }
+}
+
+fn parse_block(lines: &mut [Line], pos: &mut usize, indent: usize) -> Result<Value, YamlError> {
+    match is_sequence_item(lines[*pos].content) {
+        true => parse_sequence(lines, pos, indent),
+        false => parse_mapping(lines, pos, indent),
+    }
+}
+
+fn is_sequence_item(content: &str) -> bool {
+    content == "-" || content.starts_with("- ")
+}
+
+fn parse_sequence(lines: &mut [Line], pos: &mut usize, indent: usize) -> Result<Value, YamlError> {
+    let mut items: Vec<Value> = Vec::new();
+    while *pos < lines.len() && lines[*pos].indent == indent && is_sequence_item(lines[*pos].content) {
+        let rest = lines[*pos].content[1..].trim_start();
+        if rest.is_empty() {
+            *pos += 1;
+            items.push(nested_block(lines, pos, indent)?);
+        } else if split_key(rest).is_some() {
+            // "- key: value" opens a mapping indented like its first key.
+            let line = &mut lines[*pos];
+            line.indent += line.content.len() - rest.len();
+            line.content = rest;
+            let item_indent = line.indent;
+            items.push(parse_mapping(lines, pos, item_indent)?);
+        } else {
+            let number = lines[*pos].number;
+            *pos += 1;
+            items.push(parse_scalar(rest).map_err(|msg| (number, msg))?);
+        }
+    }
+
+    Ok(Value::Array(items))
+}
+
+fn parse_mapping(lines: &mut [Line], pos: &mut usize, indent: usize) -> Result<Value, YamlError> {
+    let mut map = Map::new();
+    while *pos < lines.len() && lines[*pos].indent == indent && !is_sequence_item(lines[*pos].content) {
+        let Line { number, content, .. } = lines[*pos];
+        let (key, rest) = match split_key(content) {
+            Some(x) => x,
+            None => return Err((number, format!("expected 'key: value', found '{content}'"))),
+        };
+        let key = match parse_scalar(key).map_err(|msg| (number, msg))? {
+            Value::String(x) => x,
+            x => x.to_string(),
+        };
+        *pos += 1;
+        let value = match rest.is_empty() {
+            true => nested_block(lines, pos, indent)?,
+            false => parse_scalar(rest).map_err(|msg| (number, msg))?,
+        };
+        if map.insert(key.clone(), value).is_some() {
+            return Err((number, format!("duplicate key '{key}'")));
+        }
+    }
+
+    Ok(Value::Object(map))
+}
+
+/// The block under a key or dash with nothing after it: anything indented
+/// further, or a sequence at the same indentation as a mapping key.
+fn nested_block(lines: &mut [Line], pos: &mut usize, indent: usize) -> Result<Value, YamlError> {
+    match lines.get(*pos) {
+        Some(line) if line.indent > indent => {
+            let indent = line.indent;
+            parse_block(lines, pos, indent)
+        }
+        Some(line) if line.indent == indent && is_sequence_item(line.content) => parse_sequence(lines, pos, indent),
+        _ => Ok(Value::Null),
+    }
+}
+
+/// Splits `key: value` at the first colon followed by a space or the end of
+/// the line, outside quotes and brackets.
+fn split_key(content: &str) -> Option<(&str, &str)> {
+    let mut quote: Option<char> = None;
+    let mut depth = 0;
+    for (i, c) in content.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '[' | '{') => depth += 1,
+            (None, ']' | '}') => depth -= 1,
+            (None, ':') if depth == 0 => {
+                let rest = &content[i + 1..];
+                if rest.is_empty() || rest.starts_with(' ') {
+                    return Some((content[..i].trim(), rest.trim()));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Cuts off a `#` comment that starts the line or follows whitespace, outside quotes.
+fn strip_comment(line: &str) -> &str {
+    let mut quote: Option<char> = None;
+    let mut previous = ' ';
+    for (i, c) in line.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '#') if previous.is_whitespace() => return &line[..i],
+            _ => {}
+        }
+        previous = c;
+    }
+
+    line
+}
+
+fn parse_scalar(text: &str) -> Result<Value, String> {
+    let mut chars = FlowReader { text, pos: 0 };
+    let value = chars.value()?;
+    chars.skip_spaces();
+    match chars.pos == text.len() {
+        true => Ok(value),
+        false => Err(format!("unexpected '{}' after a value", &text[chars.pos..])),
+    }
+}
+
+/// Reads scalars and flow collections such as `[1, 2]` or `{ name: id }`.
+struct FlowReader<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl FlowReader<'_> {
+    fn skip_spaces(&mut self) {
+        let rest = &self.text[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        self.skip_spaces();
+        match self.peek() {
+            Some('[') => self.sequence(),
+            Some('{') => self.mapping(),
+            Some(q @ ('"' | '\'')) => self.quoted(q).map(Value::String),
+            _ => Ok(plain_scalar(self.plain())),
+        }
+    }
+
+    /// A plain scalar, ending at a flow indicator or the end of the text.
+    fn plain(&mut self) -> &str {
+        let rest = &self.text[self.pos..];
+        let end = rest.find([',', ']', '}']).unwrap_or(rest.len());
+        self.pos += end;
+        rest[..end].trim()
+    }
+
+    fn quoted(&mut self, quote: char) -> Result<String, String> {
+        let mut out = String::new();
+        let mut chars = self.text[self.pos + 1..].char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                c if c == quote => {
+                    // '' is an escaped quote in a single-quoted scalar.
+                    if quote == '\'' && self.text[self.pos + 1 + i + 1..].starts_with('\'') {
+                        out.push('\'');
+                        chars.next();
+                        continue;
+                    }
+                    self.pos += 1 + i + 1;
+                    return Ok(out);
+                }
+                '\\' if quote == '"' => match chars.next() {
+                    Some((_, 'n')) => out.push('\n'),
+                    Some((_, 't')) => out.push('\t'),
+                    Some((_, x @ ('"' | '\\' | '/'))) => out.push(x),
+                    _ => return Err(String::from("unsupported escape sequence")),
+                },
+                c => out.push(c),
+            }
+        }
+
+        Err(format!("unterminated {quote}-quoted string"))
+    }
+
+    fn sequence(&mut self) -> Result<Value, String> {
+        self.pos += 1;
+        let mut items: Vec<Value> = Vec::new();
+        loop {
+            self.skip_spaces();
+            if self.peek() == Some(']') {
+                self.pos += 1;
+                return Ok(Value::Array(items));
+            }
+            items.push(self.value()?);
+            self.skip_spaces();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some(']') => {}
+                _ => return Err(String::from("expected ',' or ']' in a flow sequence")),
+            }
+        }
+    }
+
+    fn mapping(&mut self) -> Result<Value, String> {
+        self.pos += 1;
+        let mut map = Map::new();
+        loop {
+            self.skip_spaces();
+            if self.peek() == Some('}') {
+                self.pos += 1;
+                return Ok(Value::Object(map));
+            }
+            let key = match self.peek() {
+                Some(q @ ('"' | '\'')) => self.quoted(q)?,
+                _ => {
+                    let rest = &self.text[self.pos..];
+                    let end = match rest.find(':') {
+                        Some(x) => x,
+                        None => return Err(String::from("expected ':' in a flow mapping")),
+                    };
+                    self.pos += end;
+                    rest[..end].trim().to_string()
+                }
+            };
+            self.skip_spaces();
+            if self.peek() != Some(':') {
+                return Err(String::from("expected ':' in a flow mapping"));
+            }
+            self.pos += 1;
+            map.insert(key, self.value()?);
+            self.skip_spaces();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some('}') => {}
+                _ => return Err(String::from("expected ',' or '}' in a flow mapping")),
+            }
+        }
+    }
+}
+
+/// Resolves an unquoted scalar to null, a boolean, a number or a string.
+fn plain_scalar(text: &str) -> Value {
+    match text {
+        "" | "~" | "null" | "Null" | "NULL" => return Value::Null,
+        "true" | "True" | "TRUE" => return Value::Bool(true),
+        "false" | "False" | "FALSE" => return Value::Bool(false),
+        _ => {}
+    }
+    if let Ok(x) = text.parse::<i64>() {
+        return Value::from(x);
+    }
+    let looks_numeric = text.starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == '+' || c == '.');
+    match text.parse::<f64>().ok().filter(|_| looks_numeric).and_then(Number::from_f64) {
+        Some(x) => Value::Number(x),
+        None => Value::String(text.to_string()),
+    }
+}
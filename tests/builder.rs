@@ -0,0 +1,51 @@
+use joiner::{JoinerError, TableBuilder};
+
+fn input_error(builder: TableBuilder) -> String {
+    match builder.build() {
+        Err(JoinerError::Input(msg)) => msg,
+        other => panic!("expected an input error, got {other:?}"),
+    }
+}
+
+#[test]
+fn builds_a_table_sorted_on_the_given_column() {
+    let table = TableBuilder::new("Account")
+        .column("id").indexed().clustered()
+        .column("balance").distinct(320)
+        .rows(400)
+        .blocks(380)
+        .sorted_on("balance")
+        .build()
+        .unwrap();
+
+    assert_eq!((table.nr, table.br, table.shards), (400, 380, 1));
+    assert_eq!(table.columns.len(), 2);
+    assert!(table.columns[0].indexed && table.columns[0].clustered);
+    assert_eq!(table.columns[0].total_values, 400);
+    assert_eq!(table.sorted_column.as_ref(), Some(&table.columns[1]));
+}
+
+#[test]
+fn sorts_on_the_first_column_by_default() {
+    let table = TableBuilder::new("T").column("a").column("b").rows(10).blocks(1).build().unwrap();
+    assert_eq!(table.sorted_column.unwrap().name, "a");
+}
+
+#[test]
+fn rejects_a_sorted_column_that_was_not_added() {
+    let builder = TableBuilder::new("Account").column("id").rows(400).blocks(380).sorted_on("balance");
+    assert_eq!(input_error(builder), "table Account is sorted on balance, which is not one of its columns");
+}
+
+#[test]
+fn rejects_column_settings_before_any_column() {
+    let builder = TableBuilder::new("Account").indexed().column("id").rows(400).blocks(380);
+    assert_eq!(input_error(builder), "indexed was set on table Account before any of its columns was added");
+}
+
+#[test]
+fn rejects_inconsistent_statistics() {
+    let builder = TableBuilder::new("T").column("a").distinct(20).rows(10).blocks(1);
+    assert_eq!(input_error(builder), "column T.a has 20 distinct values but the table only has 10 rows");
+    assert_eq!(input_error(TableBuilder::new("T").column("a").rows(10)), "table T has no blocks (br = 0)");
+}
@@ -0,0 +1,156 @@
+use joiner::{
+    estimate_composite_join_cardinality, estimate_filtered_join_cardinality,
+    estimate_join_cardinality, selectivity, Column, Comparison, JoinType, Table,
+};
+
+fn table(name: &str, nr: u32, key_values: u32) -> Table {
+    let key = Column {
+        name: "key".to_string(),
+        indexed: false,
+        clustered: false,
+        total_values: key_values,
+        data_type: None,
+        index_group: None,
+        histogram: Vec::new(),
+        index_type: None,
+        null_count: 0,
+        index_coverage: None,
+    };
+    Table {
+        name: name.to_string(),
+        columns: vec![key.clone()],
+        sorted_column: Some(key),
+        nr,
+        br: nr / 10,
+        tuple_size: None,
+        block_size: None,
+        shards: 1,
+        memory_hint: None,
+        is_intermediate: false,
+    }
+}
+
+#[test]
+fn divides_by_larger_distinct_count() {
+    let (orders, customers) = (table("Orders", 10_000, 400), table("Customers", 500, 500));
+    let cardinality = estimate_join_cardinality(&orders, &orders.columns[0], Comparison::Eq, &customers, &customers.columns[0], JoinType::Inner);
+    assert_eq!(cardinality, 10_000);
+}
+
+#[test]
+fn zero_distinct_values_yields_empty_result() {
+    let (orders, customers) = (table("Orders", 10_000, 0), table("Customers", 500, 500));
+    let cardinality = estimate_join_cardinality(&orders, &orders.columns[0], Comparison::Eq, &customers, &customers.columns[0], JoinType::Inner);
+    assert_eq!(cardinality, 0);
+}
+
+#[test]
+fn composite_key_multiplies_selectivities() {
+    let (orders, customers) = (table("Orders", 10_000, 400), table("Customers", 500, 500));
+    let region = Column {
+        name: "region".to_string(),
+        indexed: false,
+        clustered: false,
+        total_values: 10,
+        data_type: None,
+        index_group: None,
+        histogram: Vec::new(),
+        index_type: None,
+        null_count: 0,
+        index_coverage: None,
+    };
+    let keys = [(&orders.columns[0], &customers.columns[0]), (&region, &region)];
+    assert_eq!(estimate_composite_join_cardinality(&orders, &customers, &keys, JoinType::Inner), 1_000);
+}
+
+#[test]
+fn nulls_in_the_join_key_never_match() {
+    let (mut orders, customers) = (table("Orders", 10_000, 400), table("Customers", 500, 500));
+    orders.columns[0].null_count = 7_500;
+    let cardinality = estimate_join_cardinality(&orders, &orders.columns[0], Comparison::Eq, &customers, &customers.columns[0], JoinType::Inner);
+    // Only the 2,500 orders with a customer can match: 2,500 * 500 / 500.
+    assert_eq!(cardinality, 2_500);
+}
+
+#[test]
+fn outer_joins_keep_unmatched_rows() {
+    let (orders, customers) = (table("Orders", 10_000, 400), table("Customers", 500, 500));
+    let estimate = |join_type| {
+        estimate_join_cardinality(&orders, &orders.columns[0], Comparison::Eq, &customers, &customers.columns[0], join_type)
+    };
+    assert_eq!(estimate(JoinType::LeftOuter), 10_000 + 10_000);
+    assert_eq!(estimate(JoinType::RightOuter), 10_000 + 500);
+    assert_eq!(estimate(JoinType::FullOuter), 10_000 + 10_000 + 500);
+}
+
+#[test]
+fn parses_join_type_names() {
+    assert_eq!("LEFT".parse::<JoinType>().unwrap(), JoinType::LeftOuter);
+    assert_eq!("full-outer".parse::<JoinType>().unwrap(), JoinType::FullOuter);
+    assert!("cross".parse::<JoinType>().is_err());
+}
+
+#[test]
+fn selectivity_uses_larger_distinct_count() {
+    let (uniform1, uniform2) = (table("A", 1_000, 1_000), table("B", 1_000, 1_000));
+    assert_eq!(selectivity(&uniform1.columns[0], &uniform2.columns[0]), 0.001);
+
+    // A skewed side with only a handful of values doesn't make the join less selective.
+    let (skewed, wide) = (table("A", 1_000, 4), table("B", 1_000, 1_000));
+    assert_eq!(selectivity(&skewed.columns[0], &wide.columns[0]), 0.001);
+    assert_eq!(selectivity(&skewed.columns[0], &skewed.columns[0]), 0.25);
+}
+
+#[test]
+fn selectivity_of_empty_column_is_zero() {
+    let (empty, wide) = (table("A", 1_000, 0), table("B", 1_000, 1_000));
+    assert_eq!(selectivity(&empty.columns[0], &wide.columns[0]), 0.0);
+}
+
+#[test]
+fn histograms_weigh_skewed_values() {
+    let (mut a, mut b) = (table("A", 1_000, 10), table("B", 1_000, 10));
+    let estimate = |a: &Table, b: &Table| {
+        estimate_join_cardinality(a, &a.columns[0], Comparison::Eq, b, &b.columns[0], JoinType::Inner)
+    };
+    assert_eq!(estimate(&a, &b), 100_000);
+
+    // Flat histograms agree with the uniform estimate.
+    a.columns[0].histogram = vec![500, 500];
+    b.columns[0].histogram = vec![500, 500];
+    assert_eq!(estimate(&a, &b), 100_000);
+
+    // 90% of both sides share the first half of the values: 2 * (900 * 900 + 100 * 100) / 10.
+    a.columns[0].histogram = vec![900, 100];
+    b.columns[0].histogram = vec![900, 100];
+    assert_eq!(estimate(&a, &b), 164_000);
+}
+
+#[test]
+fn equality_uses_distinct_values_and_ranges_a_third_of_the_pairs() {
+    let (a, b) = (table("A", 300, 300), table("B", 100, 50));
+    let cardinality = |op| estimate_join_cardinality(&a, &a.columns[0], op, &b, &b.columns[0], JoinType::Inner);
+    assert_eq!(cardinality(Comparison::Eq), 100);
+    assert_eq!(cardinality(Comparison::Lt), 10_000);
+    assert_eq!(cardinality(Comparison::Ne), 29_900);
+}
+
+#[test]
+fn range_selectivity_is_configurable() {
+    let (a, b) = (table("A", 300, 300), table("B", 100, 50));
+    let filters = [(&a.columns[0], Comparison::Lt, &b.columns[0])];
+    let cardinality = estimate_filtered_join_cardinality(&a, &b, &[], &filters, 0.1, JoinType::LeftOuter);
+    // A tenth of the pairs, plus every row of A kept by the left join.
+    assert_eq!(cardinality, 3_000 + 300);
+}
+
+#[test]
+fn nulls_are_ruled_out_once_per_column() {
+    let (mut orders, customers) = (table("Orders", 10_000, 400), table("Customers", 500, 500));
+    orders.columns[0].null_count = 7_500;
+    let keys = [(&orders.columns[0], &customers.columns[0])];
+    let filters = [(&orders.columns[0], Comparison::Lt, &customers.columns[0])];
+    let cardinality = estimate_filtered_join_cardinality(&orders, &customers, &keys, &filters, 0.5, JoinType::Inner);
+    // 10,000 matches halved by the range, of which the quarter of orders with a key remain.
+    assert_eq!(cardinality, 1_250);
+}
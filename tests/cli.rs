@@ -0,0 +1,43 @@
+use std::{io::Write, path::PathBuf, process::{Command, Stdio}};
+
+#[test]
+fn every_join_flag_is_estimated_in_order_under_a_header() {
+    let metadata = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/metadata.json");
+    let output = Command::new(env!("CARGO_BIN_EXE_joiner"))
+        .arg(&metadata)
+        .args(["--join", "Customer.id = Account.id", "--join", "Customer.account_no = Account.id"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let first = stdout.find("=== Join 1 of 2: Customer.id = Account.id ===").unwrap();
+    let second = stdout.find("=== Join 2 of 2: Customer.account_no = Account.id ===").unwrap();
+    assert!(first < second);
+    assert!(stdout[first..second].contains("User entered: Customer.id = Account.id"));
+    assert!(stdout[second..].contains("User entered: Customer.account_no = Account.id"));
+    assert_eq!(stdout.matches("Best cost for joining is").count(), 2);
+}
+
+#[test]
+fn a_baseline_that_costs_nothing_is_named_in_every_comparison() {
+    let metadata = r#"[
+        { "name": "A", "nr": 0, "br": 0, "columns": [{ "name": "id", "total_values": 0 }] },
+        { "name": "B", "nr": 100, "br": 10, "columns": [{ "name": "id", "total_values": 100 }] }
+    ]"#;
+    let mut child = Command::new(env!("CARGO_BIN_EXE_joiner"))
+        .args(["-", "--join", "A.id = B.id", "--baseline", "nested"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(metadata.as_bytes()).unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Compared with Nested Loop Join (0 blocks):"));
+    assert!(stdout.contains(" Block Nested Join can't be compared with a baseline that costs nothing\n"));
+    assert!(!stdout.contains(" is can't"));
+}
@@ -0,0 +1,518 @@
+use joiner::{
+    anti_join_cardinality, anti_join_cost, block_nested_fits, block_nested_join_cost,
+    block_nested_join_explain, estimated_time_ms, hash_join_cost, height_of_index_tree,
+    hybrid_hash_join_cost, index_fetch_cost, indexed_join_cost, indexed_nested_loop_cost,
+    merge_join_cost, merge_rescan_cost, merge_sort_cost, min_memory_for_hash,
+    min_memory_for_one_pass_sort, nested_loop_join_cost, no_fit_reserved_buffers, parse_tables,
+    semi_join_cost, sorting_cost, BuildSide, Column, IndexType, Table, TableBuilder, TableStats,
+};
+
+fn column(name: &str, indexed: bool, total_values: u32) -> Column {
+    Column {
+        name: name.to_string(),
+        indexed,
+        clustered: false,
+        total_values,
+        data_type: None,
+        index_group: None,
+        histogram: Vec::new(),
+        index_type: None,
+        null_count: 0,
+        index_coverage: None,
+    }
+}
+
+fn customer() -> Table {
+    TableBuilder::new("Customer").column("id").indexed().column("account_no").rows(200).blocks(150).build().unwrap()
+}
+
+fn account() -> Table {
+    TableBuilder::new("Account")
+        .column("id").indexed()
+        .column("balance").distinct(320)
+        .rows(400)
+        .blocks(380)
+        .build()
+        .unwrap()
+}
+
+/// A table sorted on its only column.
+fn table_with_column(column: Column) -> Table {
+    Table {
+        name: "T".to_string(),
+        columns: vec![column.clone()],
+        sorted_column: Some(column),
+        nr: 1,
+        br: 1,
+        tuple_size: None,
+        block_size: None,
+        shards: 1,
+        memory_hint: None,
+        is_intermediate: false,
+    }
+}
+
+#[test]
+fn block_nested_fits_in_memory() {
+    assert_eq!(block_nested_join_cost(&customer(), &account(), 10_000, 2), Some(530));
+}
+
+#[test]
+fn block_nested_reads_outer_once_in_chunks() {
+    let mut outer = customer();
+    outer.br = 50;
+    let mut inner = account();
+    inner.br = 200;
+    // ceil(50 / (12 - 2)) = 5 chunks, each scanning the inner once.
+    assert_eq!(block_nested_join_cost(&outer, &inner, 12, 2), Some(50 + 5 * 200));
+    assert_eq!(block_nested_join_cost(&inner, &outer, 12, 2), Some(50 + 5 * 200));
+}
+
+#[test]
+fn assuming_no_fit_forces_the_block_loop_for_small_tables() {
+    let (customer, account) = (customer(), account());
+    assert!(block_nested_fits(&customer, &account, 10_000, 2));
+
+    let reserved = no_fit_reserved_buffers(10_000);
+    assert!(!block_nested_fits(&customer, &account, 10_000, reserved));
+    // One outer block per chunk: the inner is scanned once per Customer block.
+    assert_eq!(block_nested_join_cost(&customer, &account, 10_000, reserved), Some(150 + 150 * 380));
+}
+
+#[test]
+fn block_nested_cost_rises_with_reserved_buffers() {
+    let mut outer = customer();
+    outer.br = 50;
+    let mut inner = account();
+    inner.br = 200;
+    // 12 - 2 = 10 blocks per chunk take 5 chunks, 12 - 7 = 5 blocks take 10.
+    assert_eq!(block_nested_join_cost(&outer, &inner, 12, 7), Some(50 + 10 * 200));
+    assert!(block_nested_join_cost(&outer, &inner, 12, 7) > block_nested_join_cost(&outer, &inner, 12, 2));
+    assert_eq!(block_nested_join_cost(&outer, &inner, 12, 12), None);
+}
+
+#[test]
+fn nested_loop_scans_inner_per_outer_tuple() {
+    assert_eq!(nested_loop_join_cost(&customer(), &account()), 200 * 380 + 150);
+}
+
+#[test]
+fn indexed_picks_cheaper_side() {
+    let (customer, account) = (customer(), account());
+    let cost = indexed_join_cost(&customer, &customer.columns[0], &account, &account.columns[0], 10);
+    // Probing Account.id for each Customer: 200 * (height 3 + 1 fetch) + 150
+    assert_eq!(cost, Some(950));
+}
+
+#[test]
+fn indexed_nested_loop_keeps_the_outer_relation_it_is_given() {
+    let (customer, account) = (customer(), account());
+    // With Account as the outer, every one of its 400 tuples probes Customer.id:
+    // 380 + 400 * (height 3 + 1 matching block).
+    let account_outer = indexed_nested_loop_cost(&account, &account.columns[0], &customer, &customer.columns[0], 10);
+    assert_eq!(account_outer, Some(380 + 400 * (3 + 1)));
+    // The indexed join turns it around and probes Account.id for each Customer.
+    let indexed = indexed_join_cost(&account, &account.columns[0], &customer, &customer.columns[0], 10);
+    assert_eq!(indexed, Some(950));
+    let customer_outer = indexed_nested_loop_cost(&customer, &customer.columns[0], &account, &account.columns[0], 10);
+    assert_eq!(customer_outer, indexed);
+    // Account.balance has no index to probe.
+    assert_eq!(indexed_nested_loop_cost(&customer, &customer.columns[1], &account, &account.columns[1], 10), None);
+}
+
+#[test]
+fn hash_index_lookup_is_constant() {
+    let customer = customer();
+    let mut account = account();
+    // A B+-tree on Account.id with fan-out 10 is 3 levels deep.
+    let btree = indexed_join_cost(&customer, &customer.columns[1], &account, &account.columns[0], 10);
+    assert_eq!(btree, Some(200 * (3 + 1) + 150));
+
+    account.columns[0].index_type = Some(IndexType::Hash);
+    let hash = indexed_join_cost(&customer, &customer.columns[1], &account, &account.columns[0], 10);
+    assert_eq!(hash, Some(200 * (1 + 1) + 150));
+}
+
+#[test]
+fn indexed_requires_an_index() {
+    let (customer, account) = (customer(), account());
+    let cost = indexed_join_cost(&customer, &customer.columns[1], &account, &account.columns[1], 10);
+    assert_eq!(cost, None);
+}
+
+#[test]
+fn composite_index_serves_its_first_column() {
+    let customer = customer();
+    let mut account = account();
+    let in_group = |name: &str| Column { index_group: Some("branch_id".to_string()), ..column(name, false, 400) };
+    account.columns = vec![in_group("branch"), in_group("id")];
+
+    // Probing the (branch, id) index for each Customer: 200 * (height 3 + 1 fetch) + 150
+    let cost = indexed_join_cost(&customer, &customer.columns[1], &account, &account.columns[0], 10);
+    assert_eq!(cost, Some(950));
+    // id isn't a prefix of the index, so it can't be looked up on its own.
+    let cost = indexed_join_cost(&customer, &customer.columns[1], &account, &account.columns[1], 10);
+    assert_eq!(cost, None);
+}
+
+#[test]
+fn clustered_index_fetches_fewer_blocks() {
+    let mut orders = account();
+    orders.nr = 10_000;
+    orders.br = 1_000;
+    let mut customer_id = column("cust_id", true, 100);
+    // 100 matching tuples, one I/O each
+    assert_eq!(index_fetch_cost(&orders, &customer_id, 0.01), 100);
+    customer_id.clustered = true;
+    // 100 matching tuples at 10 tuples per block
+    assert_eq!(index_fetch_cost(&orders, &customer_id, 0.01), 10);
+
+    let customer = customer();
+    let clustered = indexed_join_cost(&orders, &customer_id, &customer, &customer.columns[0], 10);
+    customer_id.clustered = false;
+    let unclustered = indexed_join_cost(&orders, &customer_id, &customer, &customer.columns[0], 10);
+    // Customer.id has 200 distinct values, so each customer matches 10_000 / 200 = 50 orders.
+    // height_of_index_tree(10, 100) = 2
+    assert_eq!(clustered, Some(200 * (2 + 5) + 150));
+    assert_eq!(unclustered, Some(200 * (2 + 50) + 150));
+}
+
+#[test]
+fn partial_index_fetches_its_share_and_scans_for_the_rest() {
+    let mut orders = account();
+    orders.nr = 10_000;
+    orders.br = 1_000;
+    let customer_id = Column { index_coverage: Some(0.5), ..column("cust_id", true, 100) };
+    // Only the half of the 100 matching tuples the index covers are fetched through it.
+    assert_eq!(index_fetch_cost(&orders, &customer_id, 0.01), 50);
+
+    let customer = customer();
+    let partial = indexed_join_cost(&orders, &customer_id, &customer, &customer.columns[0], 10);
+    // 25 of each customer's 50 orders come through the index, the rest from another scan of Orders.
+    assert_eq!(partial, Some(200 * (2 + 25) + 150 + 1_000));
+    let full = indexed_join_cost(&orders, &column("cust_id", true, 100), &customer, &customer.columns[0], 10);
+    assert_eq!(full, Some(200 * (2 + 50) + 150));
+
+    let too_wide = TableBuilder::new("Orders").column("cust_id").indexed().index_coverage(1.5).rows(10).blocks(1).build();
+    assert!(too_wide.is_err());
+}
+
+#[test]
+fn larger_fanout_reduces_index_height() {
+    assert_eq!(height_of_index_tree(10, 1_000_000), 6);
+    assert_eq!(height_of_index_tree(256, 1_000_000), 3);
+}
+
+#[test]
+fn single_key_index_has_one_level() {
+    assert_eq!(height_of_index_tree(10, 1), 1);
+    assert_eq!(height_of_index_tree(10, 0), 1);
+}
+
+#[test]
+fn full_three_level_index() {
+    assert_eq!(height_of_index_tree(10, 10 * 10 * 10), 3);
+    assert_eq!(height_of_index_tree(256, 256 * 256 * 256), 3);
+    assert_eq!(height_of_index_tree(256, 256 * 256 * 256 + 1), 4);
+}
+
+#[test]
+fn merge_skips_sort_on_sorted_columns() {
+    let (customer, account) = (customer(), account());
+    let cost = merge_join_cost(&customer, &customer.columns[0], &account, &account.columns[0], 10_000);
+    assert_eq!(cost, 530);
+}
+
+#[test]
+fn merge_sorts_only_the_unsorted_side() {
+    let (customer, account) = (customer(), account());
+    // Customer is sorted on id, not account_no: 150 to sort it in one run, then 150 + 380.
+    let cost = merge_join_cost(&customer, &customer.columns[1], &account, &account.columns[0], 10_000);
+    assert_eq!(cost, 680);
+}
+
+#[test]
+fn merge_sorts_both_unsorted_sides() {
+    let (customer, account) = (customer(), account());
+    let cost = merge_join_cost(&customer, &customer.columns[1], &account, &account.columns[1], 10_000);
+    assert_eq!(cost, 150 + 380 + 530);
+}
+
+#[test]
+fn intermediate_results_have_no_index_or_sort_order() {
+    let customer = customer();
+    // The result of an earlier join, whose id column was indexed and sorted in its base table.
+    let intermediate = TableBuilder::new("(Orders X Items)")
+        .column("id").indexed()
+        .rows(400)
+        .blocks(380)
+        .intermediate()
+        .build()
+        .unwrap();
+    let id = &intermediate.columns[0];
+    assert!(!intermediate.has_index_on(id) && !intermediate.is_sorted_on(id));
+
+    // Only Customer.id's index is left to probe, once for each of the 400 intermediate tuples.
+    assert_eq!(indexed_join_cost(&customer, &customer.columns[1], &intermediate, id, 10), None);
+    assert_eq!(indexed_join_cost(&customer, &customer.columns[0], &intermediate, id, 10), Some(400 * (3 + 1) + 380));
+    assert_eq!(indexed_nested_loop_cost(&customer, &customer.columns[0], &intermediate, id, 10), None);
+    // Merge join sorts the intermediate side: 380 blocks in one run, then 150 + 380.
+    assert_eq!(merge_join_cost(&customer, &customer.columns[0], &intermediate, id, 10_000), 380 + 150 + 380);
+    assert_eq!(hash_join_cost(&customer, &intermediate, 10_000, BuildSide::Auto), Some(3 * (150 + 380) + 1));
+}
+
+#[test]
+fn merge_sorts_both_sides_of_unsorted_heaps() {
+    // Metadata without a sortedColumn describes an unsorted heap.
+    let heaps = parse_tables(
+        r#"[
+            { "name": "Customer", "nr": 200, "br": 150, "columns": [{ "name": "id", "total_values": 200 }] },
+            { "name": "Account", "nr": 400, "br": 380, "columns": [{ "name": "id", "total_values": 400 }] }
+        ]"#,
+    )
+    .unwrap();
+    let (customer, account) = (&heaps[0], &heaps[1]);
+    assert_eq!(customer.sorted_column, None);
+    // Even on the first column, which a sorted table would have been sorted on.
+    let cost = merge_join_cost(customer, &customer.columns[0], account, &account.columns[0], 10_000);
+    assert_eq!(cost, 150 + 380 + 530);
+
+    let unsorted = TableBuilder::new("Customer").column("id").unsorted().rows(200).blocks(150).build().unwrap();
+    assert_eq!(merge_join_cost(&unsorted, &unsorted.columns[0], account, &account.columns[0], 10_000), cost);
+}
+
+#[test]
+fn merge_rescans_value_groups_that_do_not_fit_in_memory() {
+    let orders = Table { nr: 10_000, br: 1000, ..table_with_column(column("status", false, 10)) };
+    let items = Table { nr: 5000, br: 500, ..table_with_column(column("status", false, 10)) };
+    let (c1, c2) = (&orders.columns[0], &items.columns[0]);
+
+    // Groups of 100 and 50 blocks; 50 blocks take 5 chunks of M - 2 = 10, so
+    // the 100-block group is read 4 more times for each of the 10 values.
+    assert_eq!(merge_rescan_cost(&orders, c1, &items, c2, 12), 10 * 4 * 100);
+    assert_eq!(merge_join_cost(&orders, c1, &items, c2, 12), 1000 + 500 + 4000);
+    assert_eq!(merge_rescan_cost(&orders, c1, &items, c2, 52), 0);
+}
+
+#[test]
+fn hash_join_when_build_side_fits() {
+    assert_eq!(hash_join_cost(&customer(), &account(), 10_000, BuildSide::Auto), Some(1591));
+}
+
+#[test]
+fn sorting_cost_matches_textbook_formula() {
+    // ceil(1000 / 11) = 91 runs, ceil(log_10(91)) = 2 merge passes
+    assert_eq!(sorting_cost(1000, 11), 1000 * (2 * 2 + 1));
+}
+
+#[test]
+fn sorting_cost_single_run_is_one_pass() {
+    assert_eq!(sorting_cost(100, 200), 100);
+}
+
+#[test]
+fn hash_join_with_large_memory_does_not_overflow() {
+    assert_eq!(hash_join_cost(&customer(), &account(), 100_000, BuildSide::Auto), Some(1591));
+}
+
+#[test]
+fn hash_join_recursively_partitions_large_build_relation() {
+    let mut large1 = account();
+    large1.br = 1000;
+    let mut large2 = customer();
+    large2.br = 1000;
+    // M^2 = 1600 > 1000: partitions fit in memory.
+    assert_eq!(hash_join_cost(&large1, &large2, 40, BuildSide::Auto), Some(3 * 2000 + 26));
+    // M^2 = 100 <= 1000: ceil(log_9(1000 / 100)) = 2 extra partitioning passes.
+    assert_eq!(hash_join_cost(&large1, &large2, 10, BuildSide::Auto), Some(3 * 2000 + 101 + 2 * 2000 * 2));
+}
+
+#[test]
+fn forcing_the_larger_build_side_can_make_hash_join_infeasible() {
+    // M^2 = 225: Customer's 150 blocks partition in one pass, Account's 380 don't.
+    assert_eq!(hash_join_cost(&customer(), &account(), 15, BuildSide::Auto), Some(3 * 530 + 11));
+    assert_eq!(hash_join_cost(&customer(), &account(), 15, BuildSide::Left), Some(3 * 530 + 11));
+    assert_eq!(hash_join_cost(&customer(), &account(), 15, BuildSide::Right), None);
+}
+
+#[test]
+fn hybrid_hash_beats_hash_just_over_memory() {
+    // Customer (150 blocks) is the build relation and needs two partitions of 100 blocks.
+    let hybrid = hybrid_hash_join_cost(&customer(), &account(), 100, BuildSide::Auto);
+    assert_eq!(hybrid, Some(3 * 530 - 530));
+    assert!(hybrid < hash_join_cost(&customer(), &account(), 100, BuildSide::Auto));
+}
+
+#[test]
+fn hybrid_hash_reads_once_when_build_fits() {
+    assert_eq!(hybrid_hash_join_cost(&customer(), &account(), 200, BuildSide::Auto), Some(530));
+}
+
+#[test]
+fn build_side_memory_hint_overrides_the_global_memory() {
+    let mut customer = customer();
+    // 150 build blocks don't fit in 100 blocks of global memory...
+    assert_eq!(hybrid_hash_join_cost(&customer, &account(), 100, BuildSide::Auto), Some(3 * 530 - 530));
+    // ...but do in the 200 the build side asks for.
+    customer.memory_hint = Some(200);
+    assert_eq!(hybrid_hash_join_cost(&customer, &account(), 100, BuildSide::Auto), Some(530));
+    assert_eq!(hybrid_hash_join_cost(&account(), &customer, 100, BuildSide::Auto), Some(530));
+    // The probe side's hint doesn't count: Account's 380 blocks split into 4 partitions.
+    assert_eq!(hybrid_hash_join_cost(&customer, &account(), 100, BuildSide::Right), Some(3 * 530 - 2 * 530 / 4));
+}
+
+#[test]
+fn block_nested_explains_the_in_memory_branch() {
+    assert_eq!(
+        block_nested_join_explain(&customer(), &account(), 10_000, 2).unwrap(),
+        "Block Nested: br1 + br2 = 150 + 380 = 530 (fits in memory)"
+    );
+}
+
+#[test]
+fn block_nested_explains_the_block_loop_branch() {
+    assert_eq!(
+        block_nested_join_explain(&customer(), &account(), 100, 2).unwrap(),
+        "Block Nested: br_outer + ceil(br_outer / (M - 2)) * br_inner = 150 + 2 * 380 = 910 (outer Customer)"
+    );
+}
+
+#[test]
+fn large_tables_do_not_overflow() {
+    let mut big = account();
+    big.nr = 2_000_000;
+    big.br = 100_000;
+    let mut other = customer();
+    other.nr = 1_500_000;
+    other.br = 99_000;
+    // With a single block per chunk the inner is read 99,000 times, far beyond u32::MAX.
+    assert_eq!(block_nested_join_cost(&other, &big, 3, 2), Some(9_900_099_000));
+    assert_eq!(nested_loop_join_cost(&other, &big), 150_000_099_000);
+}
+
+#[test]
+fn min_memory_for_hash_is_where_recursion_stops() {
+    let mut big = account();
+    big.br = 10_001;
+    let threshold = min_memory_for_hash(big.br);
+    assert_eq!(threshold, 101);
+    // At the threshold there's no recursive partitioning left; one block less needs it.
+    let single_level = |memory_size: u32| 3 * 20_002 + (big.br / memory_size) as u64 + 1;
+    assert_eq!(hash_join_cost(&big, &big, threshold, BuildSide::Auto), Some(single_level(threshold)));
+    assert!(hash_join_cost(&big, &big, threshold - 1, BuildSide::Auto).unwrap() > single_level(threshold - 1));
+}
+
+#[test]
+fn min_memory_for_one_pass_sort_is_where_a_second_merge_pass_stops() {
+    for br in [1, 2, 150, 380, 10_000, 123_457] {
+        let threshold = min_memory_for_one_pass_sort(br);
+        assert!(sorting_cost(br, threshold) <= 3 * br as u64, "br = {br}");
+        if threshold > 3 {
+            assert!(sorting_cost(br, threshold - 1) > 3 * br as u64, "br = {br}");
+        }
+    }
+    assert_eq!(min_memory_for_one_pass_sort(380), 20);
+}
+
+#[test]
+fn varchar_keys_cost_more_to_sort_than_ints() {
+    let (customer, account) = (customer(), account());
+    let mut int_key = column("account_no", false, 200);
+    int_key.data_type = Some(String::from("int"));
+    let mut varchar_key = column("account_no", false, 200);
+    varchar_key.data_type = Some(String::from("VARCHAR(32)"));
+    let account_id = &account.columns[0];
+
+    let int_cost = merge_sort_cost(&customer, &int_key, &account, account_id, 13);
+    assert_eq!(int_cost, sorting_cost(customer.br, 13));
+    assert_eq!(merge_sort_cost(&customer, &varchar_key, &account, account_id, 13), 4 * int_cost);
+}
+
+#[test]
+fn bloom_filter_semi_join_beats_hash_join_at_low_selectivity() {
+    let customer = customer();
+    let mut events = account();
+    events.nr = 100_000;
+    events.br = 10_000;
+    // Only 200 of the 100,000 customer ids in Events belong to Customer.
+    let event_customer = column("customer_id", false, 100_000);
+    let semi = semi_join_cost(&customer, &customer.columns[0], &events, &event_customer, 100).unwrap();
+    let hash = hash_join_cost(&customer, &events, 100, BuildSide::Auto).unwrap();
+    assert!(semi < hash, "semi-join {semi} should beat hash join {hash}");
+    // Filling the filter and one probe scan, then hashing the ~20 passing blocks.
+    let passing = Table { nr: 200, br: 20, ..events.clone() };
+    assert_eq!(semi, 150 + 10_000 + hash_join_cost(&customer, &passing, 100, BuildSide::Auto).unwrap());
+}
+
+#[test]
+fn anti_join_keeps_the_events_of_unknown_customers() {
+    let customer = customer();
+    let mut events = account();
+    events.nr = 100_000;
+    events.br = 10_000;
+    // Only 200 of the 100,000 customer ids in Events belong to Customer, so nearly every event is kept.
+    let event_customer = column("customer_id", false, 100_000);
+    assert_eq!(anti_join_cardinality(&customer.columns[0], &events, &event_customer), 100_000 - 200);
+    // It hashes like the semi-join, only keeping the other rows.
+    let anti = anti_join_cost(&customer, &customer.columns[0], &events, &event_customer, 100);
+    assert_eq!(anti, semi_join_cost(&customer, &customer.columns[0], &events, &event_customer, 100));
+
+    // When every Customer id shows up in Events, none of the customers is kept.
+    let customer_id = column("customer_id", false, 200);
+    assert_eq!(anti_join_cardinality(&customer_id, &customer, &customer.columns[0]), 0);
+}
+
+#[test]
+fn estimated_time_multiplies_blocks_by_latency() {
+    assert_eq!(estimated_time_ms(530, 10.0), 5300.0);
+    assert_eq!(estimated_time_ms(1591, 0.5), 795.5);
+    assert_eq!(estimated_time_ms(0, 10.0), 0.0);
+}
+
+/// Statistics kept by a catalog rather than loaded into a `Table`.
+struct CatalogEntry {
+    rows: u32,
+    blocks: u32,
+    columns: Vec<Column>,
+}
+
+impl TableStats for CatalogEntry {
+    fn nr(&self) -> u32 {
+        self.rows
+    }
+
+    fn br(&self) -> u32 {
+        self.blocks
+    }
+
+    fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    fn sorted_column(&self) -> Option<&Column> {
+        self.columns.first()
+    }
+}
+
+#[test]
+fn cost_functions_accept_any_table_stats() {
+    let (customer, account) = (customer(), account());
+    let entry = CatalogEntry { rows: customer.nr, blocks: customer.br, columns: customer.columns.clone() };
+    let (id, account_id) = (&entry.columns[0], &account.columns[0]);
+
+    assert_eq!(nested_loop_join_cost(&entry, &account), nested_loop_join_cost(&customer, &account));
+    assert_eq!(block_nested_join_cost(&entry, &account, 100, 2), block_nested_join_cost(&customer, &account, 100, 2));
+    assert_eq!(indexed_join_cost(&entry, id, &account, account_id, 256), indexed_join_cost(&customer, id, &account, account_id, 256));
+    assert_eq!(merge_join_cost(&entry, id, &account, account_id, 13), merge_join_cost(&customer, id, &account, account_id, 13));
+    assert_eq!(hash_join_cost(&account, &entry, 20, BuildSide::Auto), hash_join_cost(&account, &customer, 20, BuildSide::Auto));
+    assert_eq!(
+        hybrid_hash_join_cost(&entry, &account, 20, BuildSide::Left),
+        hybrid_hash_join_cost(&customer, &account, 20, BuildSide::Left)
+    );
+    assert_eq!(
+        semi_join_cost(&entry, id, &account, account_id, 20),
+        semi_join_cost(&customer, id, &account, account_id, 20)
+    );
+    assert_eq!(anti_join_cost(&account, account_id, &entry, id, 20), anti_join_cost(&account, account_id, &customer, id, 20));
+    assert_eq!(anti_join_cardinality(account_id, &entry, id), anti_join_cardinality(account_id, &customer, id));
+}
@@ -0,0 +1,38 @@
+use joiner::{diff_metadata, load_metadata, MetadataChange, SelectivityMode, TableBuilder};
+use std::path::PathBuf;
+
+#[test]
+fn diffing_fixtures_reports_the_changed_row_count() {
+    let fixtures = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let old = load_metadata(fixtures.join("metadata.json"), SelectivityMode::Distinct).unwrap();
+    let new = load_metadata(fixtures.join("metadata-changed.json"), SelectivityMode::Distinct).unwrap();
+    let changes = diff_metadata(&old, &new);
+    assert_eq!(
+        changes,
+        vec![
+            MetadataChange::RowsChanged { table: String::from("Customer"), from: 200, to: 260 },
+            MetadataChange::BlocksChanged { table: String::from("Customer"), from: 150, to: 195 },
+            MetadataChange::ColumnAdded { table: String::from("Account"), column: String::from("opened_on") },
+        ]
+    );
+    assert_eq!(changes[0].to_string(), "~ Customer nr: 200 -> 260");
+    assert!(diff_metadata(&old, &old).is_empty());
+}
+
+#[test]
+fn diff_reports_tables_columns_and_indexes() {
+    let orders = TableBuilder::new("Orders").column("id").column("status").distinct(4).rows(100).blocks(10);
+    let old = vec![
+        orders.clone().build().unwrap(),
+        TableBuilder::new("Legacy").column("id").rows(1).blocks(1).build().unwrap(),
+    ];
+    let new = vec![
+        TableBuilder::new("Orders").column("id").indexed().rows(100).blocks(10).build().unwrap(),
+        TableBuilder::new("Refunds").column("id").rows(1).blocks(1).build().unwrap(),
+    ];
+    let changes: Vec<String> = diff_metadata(&old, &new).iter().map(|x| x.to_string()).collect();
+    assert_eq!(
+        changes,
+        ["~ Orders.id indexed: false -> true", "- column Orders.status", "- table Legacy", "+ table Refunds"]
+    );
+}
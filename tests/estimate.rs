@@ -0,0 +1,380 @@
+use joiner::{
+    candidates_to_csv, cheapest_candidate, clamp_memory_size, cost_ratio, discount_sequential_scans,
+    estimate_best_join, join_candidates, method_caveats, method_cost, parse_predicate, parse_tables,
+    resolve_join, sweep_memory, top_candidates, BuildSide, Comparison, EstimateCache, JoinEstimate,
+    JoinMethod, JoinType, JoinerError, ResolvedJoin, Table, DEFAULT_RESERVED_BUFFERS,
+    MIN_MEMORY_BLOCKS,
+};
+
+fn tables() -> Vec<Table> {
+    parse_tables(
+        r#"[
+            {
+                "name": "Customer", "nr": 200, "br": 150,
+                "columns": [
+                    { "name": "id", "indexed": true, "total_values": 200 },
+                    { "name": "account_no", "total_values": 200 }
+                ],
+                "sortedColumn": { "name": "id", "indexed": true, "total_values": 200 }
+            },
+            {
+                "name": "Account", "nr": 400, "br": 380,
+                "columns": [{ "name": "id", "total_values": 400 }],
+                "sortedColumn": { "name": "id", "total_values": 400 }
+            }
+        ]"#,
+    )
+    .unwrap()
+}
+
+#[test]
+fn wrapping_the_indexed_column_in_a_function_rules_out_indexed_joins() {
+    let tables = tables();
+    let cost = |predicate: &str, method: JoinMethod| {
+        let join = resolve_join(&tables, &parse_predicate(predicate).unwrap()).unwrap();
+        let candidates = join_candidates(&join, 10_000, 256, BuildSide::Auto, DEFAULT_RESERVED_BUFFERS);
+        candidates.into_iter().find(|(x, _)| *x == method).and_then(|(_, cost)| cost)
+    };
+    // Only Customer.id is indexed; the function hides it from lookups, not from hashing.
+    assert!(cost("Customer.id = Account.id", JoinMethod::Indexed).is_some());
+    assert_eq!(cost("UPPER(Customer.id) = Account.id", JoinMethod::Indexed), None);
+    assert_eq!(cost("Account.id = LOWER(Customer.id)", JoinMethod::IndexedNestedLoop), None);
+    assert_eq!(cost("Customer.id = UPPER(Account.id)", JoinMethod::Indexed), cost("Customer.id = Account.id", JoinMethod::Indexed));
+    assert!(cost("UPPER(Customer.id) = Account.id", JoinMethod::Hash).is_some());
+}
+
+#[test]
+fn wrapping_the_sorted_column_in_a_function_brings_back_its_sort() {
+    let mut tables = tables();
+    let merge_cost = |tables: &[Table], predicate: &str| {
+        let join = resolve_join(tables, &parse_predicate(predicate).unwrap()).unwrap();
+        method_cost(&join, JoinMethod::Merge, 100, 256, BuildSide::Auto, DEFAULT_RESERVED_BUFFERS).unwrap()
+    };
+    // Both tables are sorted on id, so merging them plainly reads each once.
+    assert_eq!(merge_cost(&tables, "Customer.id = Account.id"), 150 + 380);
+    let wrapped = merge_cost(&tables, "UPPER(Customer.id) = Account.id");
+
+    // UPPER(id) isn't ordered like id, so Customer is sorted as if it weren't sorted at all.
+    tables[0].sorted_column = None;
+    assert_eq!(wrapped, merge_cost(&tables, "Customer.id = Account.id"));
+    assert!(wrapped > 150 + 380);
+}
+
+#[test]
+fn everything_fits_in_memory() {
+    let tables = tables();
+    let join = ResolvedJoin {
+        table1: &tables[0],
+        table2: &tables[1],
+        keys: vec![(&tables[0].columns[0], &tables[1].columns[0])],
+        filters: vec![],
+        wrapped: (vec![], vec![]),
+    };
+    assert_eq!(
+        estimate_best_join(&join, JoinType::Inner, 10_000, 256, BuildSide::Auto, DEFAULT_RESERVED_BUFFERS).unwrap(),
+        JoinEstimate {
+            method: JoinMethod::BlockNested,
+            cost_blocks: 530,
+            estimated_rows: 200,
+            sort_cost: None,
+        }
+    );
+}
+
+#[test]
+fn merge_join_reports_its_sort_cost() {
+    let tables = tables();
+    let join = ResolvedJoin {
+        table1: &tables[0],
+        table2: &tables[1],
+        keys: vec![(&tables[0].columns[1], &tables[1].columns[0])],
+        filters: vec![],
+        wrapped: (vec![], vec![]),
+    };
+    // Only Customer has to be sorted: 12 runs merged in a single pass, 150 * 3 blocks.
+    assert_eq!(
+        estimate_best_join(&join, JoinType::Inner, 13, 256, BuildSide::Auto, DEFAULT_RESERVED_BUFFERS).unwrap(),
+        JoinEstimate {
+            method: JoinMethod::Merge,
+            cost_blocks: 450 + 150 + 380,
+            estimated_rows: 200,
+            sort_cost: Some(450),
+        }
+    );
+}
+
+#[test]
+fn range_join_only_allows_nested_loops_and_merge() {
+    let tables = tables();
+    let join = ResolvedJoin {
+        table1: &tables[0],
+        table2: &tables[1],
+        keys: vec![],
+        filters: vec![(&tables[0].columns[0], Comparison::Lt, &tables[1].columns[0])],
+        wrapped: (vec![], vec![]),
+    };
+    let candidates = join_candidates(&join, 13, 256, BuildSide::Auto, DEFAULT_RESERVED_BUFFERS);
+    let applicable: Vec<JoinMethod> = candidates
+        .iter()
+        .filter(|(_, cost)| cost.is_some())
+        .map(|(method, _)| *method)
+        .collect();
+    assert_eq!(applicable, vec![JoinMethod::BlockNested, JoinMethod::NestedLoop, JoinMethod::Merge]);
+}
+
+#[test]
+fn csv_marks_unavailable_methods() {
+    let tables = tables();
+    let join = ResolvedJoin {
+        table1: &tables[0],
+        table2: &tables[1],
+        keys: vec![],
+        filters: vec![(&tables[0].columns[0], Comparison::Lt, &tables[1].columns[0])],
+        wrapped: (vec![], vec![]),
+    };
+    let csv = candidates_to_csv(&join_candidates(&join, 10_000, 256, BuildSide::Auto, DEFAULT_RESERVED_BUFFERS), 80_000);
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines[0], "method,cost_blocks,estimated_rows,available");
+    assert_eq!(lines[1], "Block Nested Join,530,80000,true");
+    assert_eq!(lines[3], "Indexed Join,,80000,false");
+}
+
+#[test]
+fn forcing_an_inapplicable_method_is_an_error() {
+    let tables = tables();
+    // Neither Customer.account_no nor Account.id is indexed.
+    let join = ResolvedJoin {
+        table1: &tables[0],
+        table2: &tables[1],
+        keys: vec![(&tables[0].columns[1], &tables[1].columns[0])],
+        filters: vec![],
+        wrapped: (vec![], vec![]),
+    };
+    match method_cost(&join, JoinMethod::Indexed, 10_000, 256, BuildSide::Auto, DEFAULT_RESERVED_BUFFERS) {
+        Err(JoinerError::Input(msg)) => assert_eq!(
+            msg,
+            "method not applicable: Indexed Join can't be used without an index on a column compared for equality"
+        ),
+        other => panic!("expected an input error, got {other:?}"),
+    }
+    assert_eq!(method_cost(&join, JoinMethod::NestedLoop, 10_000, 256, BuildSide::Auto, DEFAULT_RESERVED_BUFFERS).unwrap(), 200 * 380 + 150);
+}
+
+#[test]
+fn no_applicable_method_explains_every_candidate() {
+    let tables = tables();
+    let join = ResolvedJoin {
+        table1: &tables[0],
+        table2: &tables[1],
+        keys: vec![],
+        filters: vec![(&tables[0].columns[1], Comparison::Ne, &tables[1].columns[0])],
+        wrapped: (vec![], vec![]),
+    };
+    match estimate_best_join(&join, JoinType::Inner, 2, 256, BuildSide::Auto, DEFAULT_RESERVED_BUFFERS) {
+        Err(JoinerError::Input(msg)) => {
+            assert!(msg.starts_with("no applicable join method: "), "{msg}");
+            for method in JoinMethod::ALL {
+                assert!(msg.contains(&format!("{method} can't be used with 2 blocks of memory")), "{msg}");
+            }
+        }
+        other => panic!("expected an input error, got {other:?}"),
+    }
+}
+
+#[test]
+fn one_block_of_memory_is_clamped_to_the_minimum_every_method_needs() {
+    let tables = tables();
+    let join = resolve_join(&tables, &parse_predicate("Customer.id = Account.id").unwrap()).unwrap();
+    assert!(join_candidates(&join, 1, 256, BuildSide::Auto, DEFAULT_RESERVED_BUFFERS).iter().all(|(_, cost)| cost.is_none()));
+
+    let (memory_size, warning) = clamp_memory_size(1);
+    assert_eq!(memory_size, MIN_MEMORY_BLOCKS);
+    assert!(warning.unwrap().contains("1 blocks"));
+    assert!(JoinMethod::ALL.iter().all(|x| x.min_memory(DEFAULT_RESERVED_BUFFERS) <= memory_size));
+    let candidates = join_candidates(&join, memory_size, 256, BuildSide::Auto, DEFAULT_RESERVED_BUFFERS);
+    assert!(candidates.iter().any(|(method, cost)| *method == JoinMethod::BlockNested && cost.is_some()));
+    assert_eq!(clamp_memory_size(64), (64, None));
+    assert_eq!(JoinMethod::BlockNested.min_memory(8), 9);
+}
+
+#[test]
+fn sharded_table_joins_every_shard_with_the_broadcast_side() {
+    let account = |nr: u32, br: u32, shards: u32| {
+        format!(
+            r#"{{ "name": "Account", "nr": {nr}, "br": {br}, "shards": {shards},
+                 "columns": [{{ "name": "id", "total_values": 400 }}],
+                 "sortedColumn": {{ "name": "id", "total_values": 400 }} }}"#
+        )
+    };
+    let customer = r#"{ "name": "Customer", "nr": 200, "br": 150,
+        "columns": [{ "name": "id", "total_values": 200 }],
+        "sortedColumn": { "name": "id", "total_values": 200 } }"#;
+    let cost = |account: String| {
+        let tables = parse_tables(&format!("[{customer}, {account}]")).unwrap();
+        let join = ResolvedJoin {
+            table1: &tables[0],
+            table2: &tables[1],
+            keys: vec![(&tables[0].columns[0], &tables[1].columns[0])],
+            filters: vec![],
+            wrapped: (vec![], vec![]),
+        };
+        let estimate = estimate_best_join(&join, JoinType::Inner, 10_000, 256, BuildSide::Auto, DEFAULT_RESERVED_BUFFERS).unwrap();
+        (method_cost(&join, JoinMethod::BlockNested, 10_000, 256, BuildSide::Auto, DEFAULT_RESERVED_BUFFERS).unwrap(), estimate.estimated_rows)
+    };
+
+    // One table reads Customer once; four shards each read their 95 blocks and all of Customer.
+    assert_eq!(cost(account(400, 380, 1)), (150 + 380, 200));
+    assert_eq!(cost(account(100, 95, 4)), (4 * (95 + 150), 200));
+}
+
+#[test]
+fn top_candidates_ranks_the_cheapest_first() {
+    let tables = tables();
+    let join = ResolvedJoin {
+        table1: &tables[0],
+        table2: &tables[1],
+        keys: vec![(&tables[0].columns[0], &tables[1].columns[0])],
+        filters: vec![],
+        wrapped: (vec![], vec![]),
+    };
+    let candidates = join_candidates(&join, 20, 256, BuildSide::Auto, DEFAULT_RESERVED_BUFFERS);
+    assert_eq!(
+        top_candidates(&candidates, 3),
+        vec![(JoinMethod::Merge, 530), (JoinMethod::Indexed, 1180), (JoinMethod::HybridHash, 1458)]
+    );
+}
+
+#[test]
+fn cost_ratio_compares_with_the_baseline() {
+    assert_eq!(cost_ratio(530, 3570).map(|x| (1.0 / x * 10.0).round() / 10.0), Some(6.7));
+    assert_eq!(cost_ratio(3570, 530).map(|x| (x * 10.0).round() / 10.0), Some(6.7));
+    assert_eq!(cost_ratio(530, 0), None);
+}
+
+#[test]
+fn ties_go_to_the_higher_priority_method() {
+    // Listed out of priority order, so the winner can't come from candidate order.
+    let candidates = [
+        (JoinMethod::Hash, Some(700)),
+        (JoinMethod::Merge, Some(700)),
+        (JoinMethod::NestedLoop, Some(900)),
+        (JoinMethod::Indexed, None),
+    ];
+    assert_eq!(cheapest_candidate(&candidates), Some((JoinMethod::Merge, 700)));
+    assert_eq!(top_candidates(&candidates, 2), vec![(JoinMethod::Merge, 700), (JoinMethod::Hash, 700)]);
+}
+
+#[test]
+fn cache_estimates_a_repeated_join_once() {
+    let tables = tables();
+    let join = || ResolvedJoin {
+        table1: &tables[0],
+        table2: &tables[1],
+        keys: vec![(&tables[0].columns[0], &tables[1].columns[0])],
+        filters: vec![],
+        wrapped: (vec![], vec![]),
+    };
+    let mut cache = EstimateCache::new();
+    let first = cache.estimate(&join(), JoinType::Inner, 13, 256, BuildSide::Auto, DEFAULT_RESERVED_BUFFERS).unwrap();
+    let second = cache.estimate(&join(), JoinType::Inner, 13, 256, BuildSide::Auto, DEFAULT_RESERVED_BUFFERS).unwrap();
+    assert_eq!(first, second);
+    assert_eq!(cache.computed(), 1);
+
+    // A different memory size is a different estimate.
+    cache.estimate(&join(), JoinType::Inner, 20, 256, BuildSide::Auto, DEFAULT_RESERVED_BUFFERS).unwrap();
+    assert_eq!(cache.computed(), 2);
+}
+
+#[test]
+fn hash_join_warns_about_skewed_keys() {
+    let tables = parse_tables(
+        r#"[
+            {
+                "name": "Orders", "nr": 10000, "br": 1000,
+                "columns": [{ "name": "status", "total_values": 5 }],
+                "sortedColumn": { "name": "status", "total_values": 5 }
+            },
+            {
+                "name": "Events", "nr": 20000, "br": 2000,
+                "columns": [{ "name": "status", "total_values": 5 }],
+                "sortedColumn": { "name": "status", "total_values": 5 }
+            }
+        ]"#,
+    )
+    .unwrap();
+    let join = ResolvedJoin {
+        table1: &tables[0],
+        table2: &tables[1],
+        keys: vec![(&tables[0].columns[0], &tables[1].columns[0])],
+        filters: vec![],
+        wrapped: (vec![], vec![]),
+    };
+
+    let caveats = method_caveats(&join, JoinMethod::Hash, 50, BuildSide::Auto);
+    assert_eq!(caveats.len(), 1);
+    assert!(caveats[0].contains("Orders.status has only 5 distinct values for 10000 rows"), "{}", caveats[0]);
+    assert!(caveats[0].contains("about 200 blocks, more than the 48 blocks"), "{}", caveats[0]);
+    assert!(method_caveats(&join, JoinMethod::BlockNested, 50, BuildSide::Auto).is_empty());
+}
+
+#[test]
+fn evenly_spread_keys_have_no_caveats() {
+    let tables = tables();
+    let join = ResolvedJoin {
+        table1: &tables[0],
+        table2: &tables[1],
+        keys: vec![(&tables[0].columns[0], &tables[1].columns[0])],
+        filters: vec![],
+        wrapped: (vec![], vec![]),
+    };
+    for method in JoinMethod::ALL {
+        assert!(method_caveats(&join, method, 20, BuildSide::Auto).is_empty(), "{method}");
+    }
+}
+
+#[test]
+fn memory_sweep_shows_where_the_best_method_changes() {
+    let tables = tables();
+    let join = ResolvedJoin {
+        table1: &tables[0],
+        table2: &tables[1],
+        keys: vec![(&tables[0].columns[0], &tables[1].columns[0])],
+        filters: vec![],
+        wrapped: (vec![], vec![]),
+    };
+    // Both sides are sorted on id, so merge join reads each once. Block nested
+    // loop does too once Customer's 150 blocks fit beside 2 buffers, and wins the tie.
+    let sweep = sweep_memory(&join, &[2, 151, 152], 256, BuildSide::Auto, DEFAULT_RESERVED_BUFFERS);
+    assert_eq!(
+        sweep,
+        vec![(2, None), (151, Some((JoinMethod::Merge, 530))), (152, Some((JoinMethod::BlockNested, 530)))]
+    );
+}
+
+#[test]
+fn methods_serialize_as_their_names() {
+    assert_eq!(serde_json::to_string(&JoinMethod::HybridHash).unwrap(), r#""Hybrid Hash Join""#);
+    assert_eq!(serde_json::to_string(&[JoinMethod::BlockNested, JoinMethod::Merge]).unwrap(), r#"["Block Nested Join","Merge Join"]"#);
+}
+
+#[test]
+fn sequential_discount_lowers_merge_but_not_index_fetches() {
+    let tables = tables();
+    let join = ResolvedJoin {
+        table1: &tables[0],
+        table2: &tables[1],
+        keys: vec![(&tables[0].columns[0], &tables[1].columns[0])],
+        filters: vec![],
+        wrapped: (vec![], vec![]),
+    };
+    let cost = |candidates: &[(JoinMethod, Option<u64>)], method: JoinMethod| {
+        candidates.iter().find(|(x, _)| *x == method).and_then(|(_, cost)| *cost)
+    };
+    let mut candidates = join_candidates(&join, 10_000, 256, BuildSide::Auto, DEFAULT_RESERVED_BUFFERS);
+    discount_sequential_scans(&mut candidates, 0.3);
+    // Both sides are sorted on id, so merge join just scans 150 + 380 blocks.
+    assert_eq!(cost(&candidates, JoinMethod::Merge), Some(371));
+    assert_eq!(cost(&candidates, JoinMethod::BlockNested), Some(371));
+    // Every Account tuple still probes Customer.id at random: 380 + 400 * (1 + 1).
+    assert_eq!(cost(&candidates, JoinMethod::Indexed), Some(1180));
+}
@@ -0,0 +1,230 @@
+use joiner::{
+    check_non_interactive, memory_blocks_from_bytes, parse_byte_size, parse_memory_size,
+    parse_memory_sweep, parse_predicate, read_predicates_file, resolve_memory, resolve_memory_size,
+    Comparison, JoinCondition, JoinerError, Predicate, DEFAULT_MEMORY_SIZE,
+};
+use std::{env, fs, process};
+
+fn condition(left: (&str, &str), op: Comparison, right: (&str, &str)) -> JoinCondition {
+    JoinCondition {
+        left: (left.0.to_string(), left.1.to_string()),
+        op,
+        right: (right.0.to_string(), right.1.to_string()),
+        left_function: None,
+        right_function: None,
+    }
+}
+
+fn column_pair(left: (&str, &str), right: (&str, &str)) -> JoinCondition {
+    condition(left, Comparison::Eq, right)
+}
+
+fn input_error(input: &str) -> String {
+    syntax_error(input).0
+}
+
+fn syntax_error(input: &str) -> (String, (usize, usize)) {
+    match parse_predicate(input) {
+        Err(JoinerError::Syntax { message, span }) => (message, span),
+        other => panic!("expected a syntax error for {input:?}, got {other:?}"),
+    }
+}
+
+#[test]
+fn parses_well_formed_predicate() {
+    let parsed = parse_predicate("Orders.cust_id = Customers.id\n").unwrap();
+    assert_eq!(parsed, vec![column_pair(("Orders", "cust_id"), ("Customers", "id"))]);
+}
+
+#[test]
+fn parses_conjunction_of_conditions() {
+    let parsed = parse_predicate("A.x = B.x AND B.y = A.y and A.z=B.w").unwrap();
+    assert_eq!(
+        parsed,
+        vec![
+            column_pair(("A", "x"), ("B", "x")),
+            column_pair(("A", "y"), ("B", "y")),
+            column_pair(("A", "z"), ("B", "w")),
+        ]
+    );
+}
+
+#[test]
+fn parses_comparison_operators() {
+    let parsed = parse_predicate("A.x = B.x AND A.y<B.y AND B.z >= A.z AND A.w <> B.w").unwrap();
+    assert_eq!(
+        parsed,
+        vec![
+            column_pair(("A", "x"), ("B", "x")),
+            condition(("A", "y"), Comparison::Lt, ("B", "y")),
+            // Reoriented to match the first condition, so the comparison flips too.
+            condition(("A", "z"), Comparison::Le, ("B", "z")),
+            condition(("A", "w"), Comparison::Ne, ("B", "w")),
+        ]
+    );
+}
+
+#[test]
+fn parses_a_function_wrapping_a_column() {
+    let parsed = parse_predicate("B.y = UPPER( A.x ) AND A.z = B.z").unwrap();
+    let mut expected = column_pair(("B", "y"), ("A", "x"));
+    expected.right_function = Some(String::from("UPPER"));
+    assert_eq!(parsed[0], expected);
+    assert_eq!(parsed[0].to_string(), "B.y = UPPER(A.x)");
+    // Flipping the condition moves the function with its column.
+    assert_eq!(parsed[0].clone().flipped().to_string(), "UPPER(A.x) = B.y");
+    assert_eq!(input_error("A.x = (B.x)"), "invalid function name in right side '(B.x)', expected FUNC(<table>.<column>)");
+}
+
+#[test]
+fn unknown_comparison_operator() {
+    assert_eq!(
+        input_error("A.x ! B.x"),
+        "unknown comparison operator in 'A.x ! B.x', expected one of =, !=, <, <=, >, >="
+    );
+}
+
+#[test]
+fn conjunction_must_join_the_same_tables() {
+    assert_eq!(
+        input_error("A.x = B.x AND A.y = C.y"),
+        "every condition must join A with B, found A and C"
+    );
+}
+
+#[test]
+fn dangling_and() {
+    assert_eq!(input_error("A.x = B.x AND"), "empty condition around AND in 'A.x = B.x AND'");
+}
+
+#[test]
+fn missing_equals_sign() {
+    assert_eq!(
+        input_error("Orders.cust_id"),
+        "missing '=' in 'Orders.cust_id', expected <table1>.<column1> = <table2>.<column2>"
+    );
+}
+
+#[test]
+fn missing_dot_on_right_side() {
+    assert_eq!(
+        input_error("a.b = c"),
+        "missing '.' in right side 'c', expected <table1>.<column1> = <table2>.<column2>"
+    );
+}
+
+#[test]
+fn syntax_errors_point_at_the_offending_bytes() {
+    let input = "Orders.cust_id = Customers";
+    let (_, (start, end)) = syntax_error(input);
+    assert_eq!((start, end), (17, 26));
+    assert_eq!(&input[start..end], "Customers");
+
+    let input = "A.x = B.x AND  A.y =  c ";
+    let (_, (start, end)) = syntax_error(input);
+    assert_eq!(&input[start..end], "c");
+    assert_eq!(syntax_error("A.x = B.x AND").1, (10, 13));
+}
+
+#[test]
+fn empty_left_side() {
+    assert_eq!(
+        input_error("= x.y"),
+        "missing '.' in left side '', expected <table1>.<column1> = <table2>.<column2>"
+    );
+}
+
+#[test]
+fn missing_column_name() {
+    assert_eq!(input_error("a. = x.y"), "missing column name in left side 'a.'");
+}
+
+#[test]
+fn memory_size_suffixes() {
+    assert_eq!(parse_memory_size("10000").unwrap(), 10_000);
+    assert_eq!(parse_memory_size("64K").unwrap(), 64 * 1024);
+    assert_eq!(parse_memory_size("2m").unwrap(), 2 * 1024 * 1024);
+}
+
+#[test]
+fn memory_size_rejects_unknown_suffix() {
+    match parse_memory_size("64G") {
+        Err(JoinerError::Input(msg)) => assert_eq!(
+            msg,
+            "invalid memory size '64G', expected a whole number of blocks optionally followed by K or M"
+        ),
+        other => panic!("expected an input error, got {other:?}"),
+    }
+}
+
+#[test]
+fn predicates_file_skips_comments_and_keeps_bad_lines() {
+    let path = env::temp_dir().join(format!("joiner-{}-predicates.txt", process::id()));
+    fs::write(&path, "# customers with their accounts\nCustomer.id = Account.id\nCustomer.id\n").unwrap();
+    let predicates = read_predicates_file(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(predicates.len(), 2);
+    assert_eq!(predicates[0].0, 2);
+    assert_eq!(predicates[0].1.as_ref().unwrap(), &vec![column_pair(("Customer", "id"), ("Account", "id"))]);
+    assert_eq!(predicates[1].0, 3);
+    assert!(matches!(&predicates[1].1, Err(JoinerError::Syntax { message, .. }) if message.starts_with("missing '='")));
+}
+
+#[test]
+fn predicate_displays_in_canonical_form() {
+    let predicate: Predicate = "  Orders.cust_id   =Customers.id and Customers.since<= Orders.placed ".parse().unwrap();
+    assert_eq!(predicate.to_string(), "Orders.cust_id = Customers.id AND Orders.placed >= Customers.since");
+}
+
+#[test]
+fn memory_size_prefers_the_command_line_then_the_environment() {
+    assert_eq!(resolve_memory_size(Some("64K"), Some("20000")).unwrap(), 64 * 1024);
+    assert_eq!(resolve_memory_size(None, Some("20000")).unwrap(), 20_000);
+    assert_eq!(resolve_memory_size(None, None).unwrap(), DEFAULT_MEMORY_SIZE);
+    match resolve_memory_size(None, Some("lots")) {
+        Err(JoinerError::Input(msg)) => assert!(msg.starts_with("SQL_JOINER_MEMORY: invalid memory size 'lots'"), "{msg}"),
+        other => panic!("expected an input error, got {other:?}"),
+    }
+}
+
+#[test]
+fn memory_sweep_includes_the_end() {
+    assert_eq!(parse_memory_sweep("10:30:10").unwrap(), vec![10, 20, 30]);
+    assert_eq!(parse_memory_sweep("1K:2K:512").unwrap(), vec![1024, 1536, 2048]);
+    assert!(parse_memory_sweep("30:10:10").is_err());
+    assert!(parse_memory_sweep("10:30:0").is_err());
+    assert!(parse_memory_sweep("10:30").is_err());
+}
+
+#[test]
+fn memory_in_bytes_is_converted_to_blocks() {
+    assert_eq!(parse_byte_size("8G").unwrap(), 8 * 1024 * 1024 * 1024);
+    assert_eq!(parse_byte_size("512MB").unwrap(), 512 * 1024 * 1024);
+    assert_eq!(parse_byte_size("4096B").unwrap(), 4096);
+    assert_eq!(memory_blocks_from_bytes(8 * 1024 * 1024 * 1024, 4096).unwrap(), 2 * 1024 * 1024);
+    assert_eq!(memory_blocks_from_bytes(10_000, 4096).unwrap(), 2);
+    assert!(memory_blocks_from_bytes(1000, 4096).is_err());
+    assert_eq!(resolve_memory(None, Some("40M"), 8192, Some("20000")).unwrap(), 5120);
+}
+
+#[test]
+fn memory_in_blocks_and_bytes_conflict() {
+    match resolve_memory(Some("10000"), Some("8G"), 4096, None) {
+        Err(JoinerError::Usage(msg)) => assert!(msg.contains("--memory-bytes"), "{msg}"),
+        other => panic!("expected a usage error, got {other:?}"),
+    }
+    assert_eq!(resolve_memory(Some("10000"), None, 4096, None).unwrap(), 10_000);
+}
+
+#[test]
+fn non_interactive_runs_need_the_predicate_as_an_argument() {
+    match check_non_interactive(None, false) {
+        Err(JoinerError::Usage(msg)) => {
+            assert_eq!(msg, "--no-interactive needs the join predicate to be given with --join")
+        }
+        other => panic!("expected a usage error, got {other:?}"),
+    }
+    assert!(check_non_interactive(Some("Orders.cust_id = Customers.id"), false).is_ok());
+    assert!(matches!(check_non_interactive(Some("Orders.cust_id = Customers.id"), true), Err(JoinerError::Usage(_))));
+}
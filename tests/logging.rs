@@ -0,0 +1,44 @@
+use joiner::{join_candidates, parse_predicate, resolve_join, BuildSide, TableBuilder, DEFAULT_RESERVED_BUFFERS};
+use log::{LevelFilter, Log, Metadata, Record};
+use std::sync::Mutex;
+
+/// Keeps every message logged, so a test can check what the cost functions said.
+struct CapturingLogger {
+    messages: Mutex<Vec<String>>,
+}
+
+impl Log for CapturingLogger {
+    fn enabled(&self, _: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        self.messages.lock().unwrap().push(record.args().to_string());
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: CapturingLogger = CapturingLogger { messages: Mutex::new(Vec::new()) };
+
+#[test]
+fn debug_logging_leaves_the_costs_unchanged() {
+    let tables = vec![
+        TableBuilder::new("Customer").column("id").indexed().rows(200).blocks(150).build().unwrap(),
+        TableBuilder::new("Account").column("id").rows(400).blocks(380).build().unwrap(),
+    ];
+    let join = resolve_join(&tables, &parse_predicate("Customer.id = Account.id").unwrap()).unwrap();
+    log::set_logger(&LOGGER).unwrap();
+    let costs = |memory_size: u32| join_candidates(&join, memory_size, 256, BuildSide::Auto, DEFAULT_RESERVED_BUFFERS);
+
+    log::set_max_level(LevelFilter::Off);
+    let quiet = [costs(20), costs(10_000)];
+    assert!(LOGGER.messages.lock().unwrap().is_empty());
+
+    log::set_max_level(LevelFilter::Debug);
+    let logged = [costs(20), costs(10_000)];
+    assert_eq!(logged, quiet);
+    let messages = LOGGER.messages.lock().unwrap();
+    assert!(messages.iter().any(|x| x.starts_with("hash: build of 150 blocks")), "{messages:?}");
+    assert!(messages.iter().any(|x| x.starts_with("block nested: outer of 150 blocks fits")), "{messages:?}");
+}
@@ -0,0 +1,116 @@
+//! Checks every cost function over a grid of table sizes and memory sizes:
+//! a bigger table never makes a join cheaper, and more memory never makes
+//! it more expensive or infeasible.
+//!
+//! A table grows with its distinct counts, keeping every key unique. More
+//! distinct values at the same size rightly make a join cheaper, since
+//! index lookups fetch fewer tuples and merge join's value groups shrink, so
+//! that direction isn't a regression and isn't checked. A table can also
+//! grow out of a method: hybrid hash join stops applying once M^2 <= br,
+//! which only compares costs where both sizes have one.
+
+use joiner::{
+    block_nested_join_cost, hash_join_cost, hybrid_hash_join_cost, indexed_join_cost,
+    merge_join_cost, nested_loop_join_cost, sorting_cost, BuildSide, Table, TableBuilder,
+    DEFAULT_RESERVED_BUFFERS,
+};
+
+const BLOCKS: [u32; 7] = [1, 7, 50, 400, 3_000, 25_000, 200_000];
+const MEMORY: [u32; 9] = [3, 4, 5, 10, 30, 100, 1_000, 50_000, 1_000_000];
+/// Tuples per block of every table.
+const ROWS_PER_BLOCK: u32 = 10;
+const FANOUT: u32 = 256;
+
+/// A table of `br` blocks joined on `key`, indexed or not; it's sorted on
+/// another column, so merge join always sorts it.
+fn table(name: &str, br: u32, indexed: bool) -> Table {
+    let builder = TableBuilder::new(name).column("other").column("key");
+    let builder = if indexed { builder.indexed() } else { builder };
+    builder.rows(br * ROWS_PER_BLOCK).blocks(br).build().unwrap()
+}
+
+/// Every method's cost, in a fixed order, `None` where it doesn't apply.
+fn costs(table1: &Table, table2: &Table, memory_size: u32) -> [(&'static str, Option<u64>); 6] {
+    let (key1, key2) = (&table1.columns[1], &table2.columns[1]);
+    [
+        ("block nested", block_nested_join_cost(table1, table2, memory_size, DEFAULT_RESERVED_BUFFERS)),
+        ("nested loop", Some(nested_loop_join_cost(table1, table2))),
+        ("indexed", indexed_join_cost(table1, key1, table2, key2, FANOUT)),
+        ("merge", Some(merge_join_cost(table1, key1, table2, key2, memory_size))),
+        ("hash", hash_join_cost(table1, table2, memory_size, BuildSide::Auto)),
+        ("hybrid hash", hybrid_hash_join_cost(table1, table2, memory_size, BuildSide::Auto)),
+    ]
+}
+
+/// Calls `check` with every combination of sizes and index flags.
+fn for_each_join(mut check: impl FnMut(u32, u32, bool, u32)) {
+    for br1 in BLOCKS {
+        for br2 in BLOCKS {
+            for indexed in [false, true] {
+                for memory_size in MEMORY {
+                    check(br1, br2, indexed, memory_size);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn growing_either_table_never_lowers_the_cost() {
+    for_each_join(|br1, br2, indexed, memory_size| {
+        let before = costs(&table("T1", br1, false), &table("T2", br2, indexed), memory_size);
+        for bigger in BLOCKS.into_iter().filter(|x| *x > br1) {
+            let after = costs(&table("T1", bigger, false), &table("T2", br2, indexed), memory_size);
+            for ((method, old), (_, new)) in before.iter().zip(&after) {
+                if let (Some(old), Some(new)) = (old, new) {
+                    assert!(new >= old, "{method}: br1 {br1} -> {bigger}, br2 {br2}, M {memory_size}: {old} -> {new}");
+                }
+            }
+        }
+        for bigger in BLOCKS.into_iter().filter(|x| *x > br2) {
+            let after = costs(&table("T1", br1, false), &table("T2", bigger, indexed), memory_size);
+            for ((method, old), (_, new)) in before.iter().zip(&after) {
+                if let (Some(old), Some(new)) = (old, new) {
+                    assert!(new >= old, "{method}: br1 {br1}, br2 {br2} -> {bigger}, M {memory_size}: {old} -> {new}");
+                }
+            }
+        }
+    });
+}
+
+#[test]
+fn more_memory_never_raises_the_cost_or_rules_a_method_out() {
+    for_each_join(|br1, br2, indexed, memory_size| {
+        let (table1, table2) = (table("T1", br1, false), table("T2", br2, indexed));
+        let before = costs(&table1, &table2, memory_size);
+        for more in MEMORY.into_iter().filter(|x| *x > memory_size) {
+            let after = costs(&table1, &table2, more);
+            for ((method, old), (_, new)) in before.iter().zip(&after) {
+                match (old, new) {
+                    (Some(old), Some(new)) => assert!(
+                        new <= old,
+                        "{method}: br1 {br1}, br2 {br2}, M {memory_size} -> {more}: {old} -> {new}"
+                    ),
+                    (Some(_), None) => panic!("{method}: br1 {br1}, br2 {br2}, M {memory_size} -> {more}: no longer applies"),
+                    _ => {}
+                }
+            }
+        }
+    });
+}
+
+#[test]
+fn sorting_cost_grows_with_the_input_and_shrinks_with_memory() {
+    for br in BLOCKS {
+        for memory_size in MEMORY {
+            let cost = sorting_cost(br, memory_size);
+            assert!(cost >= br as u64, "br {br}, M {memory_size}: {cost}");
+            for bigger in BLOCKS.into_iter().filter(|x| *x > br) {
+                assert!(sorting_cost(bigger, memory_size) >= cost, "br {br} -> {bigger}, M {memory_size}");
+            }
+            for more in MEMORY.into_iter().filter(|x| *x > memory_size) {
+                assert!(sorting_cost(br, more) <= cost, "br {br}, M {memory_size} -> {more}");
+            }
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use joiner::{
+    best_join_order, parse_conditions, parse_tables, BuildSide, JoinMethod, JoinerError, PlanNode,
+    Table, DEFAULT_RESERVED_BUFFERS,
+};
+
+fn table(name: &str, nr: u32, br: u32, columns: &[(&str, u32)]) -> String {
+    let columns: Vec<String> = columns
+        .iter()
+        .map(|(column, values)| format!(r#"{{ "name": "{column}", "total_values": {values} }}"#))
+        .collect();
+    format!(
+        r#"{{ "name": "{name}", "nr": {nr}, "br": {br}, "columns": [{}], "sortedColumn": {} }}"#,
+        columns.join(", "),
+        columns[0]
+    )
+}
+
+fn chain() -> Vec<Table> {
+    let tables = [
+        table("Customer", 200, 150, &[("id", 200)]),
+        table("Orders", 5_000, 1_000, &[("id", 5_000), ("cust_id", 200)]),
+        table("Item", 20_000, 2_000, &[("id", 20_000), ("order_id", 5_000)]),
+    ];
+    parse_tables(&format!("[{}]", tables.join(", "))).unwrap()
+}
+
+#[test]
+fn finds_connected_left_deep_order() {
+    let conditions = parse_conditions("Customer.id = Orders.cust_id AND Orders.id = Item.order_id").unwrap();
+    let plan = best_join_order(&chain(), &conditions, 100, 256, BuildSide::Auto, DEFAULT_RESERVED_BUFFERS, false).unwrap();
+
+    let mut order = vec![plan.first.clone()];
+    order.extend(plan.steps.iter().map(|step| step.table.clone()));
+    // Customer and Item aren't connected, so they can't be the first pair.
+    assert_eq!(order.len(), 3);
+    assert!(order[..2].contains(&"Orders".to_string()), "{order:?}");
+    assert_eq!(plan.total_cost, plan.steps.iter().map(|step| step.cost).sum::<u64>());
+}
+
+#[test]
+fn rejects_disconnected_tables() {
+    let tables = chain();
+    let conditions = parse_conditions("Customer.id = Orders.cust_id AND Item.id = Item.order_id").unwrap();
+    match best_join_order(&tables, &conditions, 100, 256, BuildSide::Auto, DEFAULT_RESERVED_BUFFERS, false) {
+        Err(JoinerError::Input(msg)) => {
+            assert_eq!(msg, "the join conditions don't connect all of Customer, Orders, Item")
+        }
+        other => panic!("expected an input error, got {other:?}"),
+    }
+}
+
+#[test]
+fn caps_the_number_of_tables() {
+    let names = ["A", "B", "C", "D", "E", "F", "G"];
+    let tables: Vec<String> = names.iter().map(|name| table(name, 10, 1, &[("id", 10)])).collect();
+    let tables = parse_tables(&format!("[{}]", tables.join(", "))).unwrap();
+    let conditions: Vec<String> = names.windows(2).map(|w| format!("{}.id = {}.id", w[0], w[1])).collect();
+    let conditions = parse_conditions(&conditions.join(" AND ")).unwrap();
+    match best_join_order(&tables, &conditions, 100, 256, BuildSide::Auto, DEFAULT_RESERVED_BUFFERS, false) {
+        Err(JoinerError::Input(msg)) => {
+            assert_eq!(msg, "join orders can be enumerated for at most 6 tables, got 7")
+        }
+        other => panic!("expected an input error, got {other:?}"),
+    }
+}
+
+#[test]
+fn materializing_results_changes_the_order() {
+    let tables = [
+        table("A", 1_000, 333, &[("x", 448)]),
+        table("B", 1_000, 50, &[("x", 125), ("y", 70)]),
+        table("C", 200, 13, &[("y", 73)]),
+    ];
+    let tables = parse_tables(&format!("[{}]", tables.join(", "))).unwrap();
+    let conditions = parse_conditions("A.x = B.x AND B.y = C.y").unwrap();
+    let first_pair = |materialize: bool| {
+        let plan = best_join_order(&tables, &conditions, 20, 256, BuildSide::Auto, DEFAULT_RESERVED_BUFFERS, materialize).unwrap();
+        let mut pair = vec![plan.first, plan.steps[0].table.clone()];
+        pair.sort();
+        pair
+    };
+
+    // A X B has fewer rows than B X C (2,232 against 2,740), but A's wide
+    // tuples make it 855 blocks to write against 315.
+    assert_eq!(first_pair(false), ["A", "B"]);
+    assert_eq!(first_pair(true), ["B", "C"]);
+}
+
+#[test]
+fn plan_tree_nests_every_step_under_the_next() {
+    let conditions = parse_conditions("Customer.id = Orders.cust_id AND Orders.id = Item.order_id").unwrap();
+    let plan = best_join_order(&chain(), &conditions, 100, 256, BuildSide::Auto, DEFAULT_RESERVED_BUFFERS, false).unwrap();
+    let (first, second) = (&plan.steps[0], &plan.steps[1]);
+
+    let scan = |table: &str| Box::new(PlanNode::Scan { table: table.to_string() });
+    let expected = PlanNode::Join {
+        method: second.method,
+        cost: second.cost,
+        total_cost: plan.total_cost,
+        estimated_rows: second.estimated_rows,
+        left: Box::new(PlanNode::Join {
+            method: first.method,
+            cost: first.cost,
+            total_cost: first.cost,
+            estimated_rows: first.estimated_rows,
+            left: scan(&plan.first),
+            right: scan(&first.table),
+        }),
+        right: scan(&second.table),
+    };
+    assert_eq!(plan.plan_tree(), expected);
+
+    let lines: Vec<String> = plan.plan_tree().to_string().lines().map(String::from).collect();
+    assert_eq!(lines.len(), 5);
+    assert!(lines[0].starts_with(&format!("{}: {} blocks, {} in total", second.method, second.cost, plan.total_cost)));
+    assert!(lines[1].starts_with(&format!("  {}: ", first.method)));
+    assert_eq!(lines[2..], [format!("    {}", plan.first), format!("    {}", first.table), format!("  {}", second.table)]);
+}
+
+#[test]
+fn reserved_buffers_shrink_the_memory_of_every_step() {
+    let tables = [table("A", 100, 10, &[("x", 100)]), table("B", 1_000, 100, &[("x", 1_000)])];
+    let tables = parse_tables(&format!("[{}]", tables.join(", "))).unwrap();
+    let conditions = parse_conditions("A.x = B.x").unwrap();
+    let step = |reserved_buffers| {
+        best_join_order(&tables, &conditions, 12, 256, BuildSide::Auto, reserved_buffers, false).unwrap().steps[0].clone()
+    };
+
+    // All of A fits next to the default two reserved buffers, so block nested
+    // loop reads each table once; with eight reserved it no longer does, and
+    // merge join, which reads each sorted table once too, takes its place.
+    let (default, reserved) = (step(DEFAULT_RESERVED_BUFFERS), step(8));
+    assert_eq!((default.method, default.cost), (JoinMethod::BlockNested, 110));
+    assert_eq!((reserved.method, reserved.cost), (JoinMethod::Merge, 110));
+}
@@ -0,0 +1,11 @@
+use joiner::{pseudocode, JoinMethod};
+
+#[test]
+fn every_method_starts_with_its_own_header() {
+    for method in JoinMethod::ALL {
+        let header = pseudocode(method).lines().next().unwrap();
+        assert_eq!(header, format!("{method}(R, S):"));
+    }
+    assert!(pseudocode(JoinMethod::Hash).contains("build an in-memory hash table"));
+    assert!(pseudocode(JoinMethod::Merge).contains("sort R and S on the join column"));
+}
@@ -0,0 +1,154 @@
+use joiner::{
+    join_report, parse_tables, CostModel, JoinMethod, JoinerError, Predicate, ReportOptions, Table,
+};
+
+fn tables() -> Vec<Table> {
+    parse_tables(
+        r#"[
+            {
+                "name": "Customer", "nr": 200, "br": 150,
+                "columns": [
+                    { "name": "id", "indexed": true, "total_values": 200 },
+                    { "name": "tier", "total_values": 2 }
+                ],
+                "sortedColumn": { "name": "id", "indexed": true, "total_values": 200 }
+            },
+            {
+                "name": "Account", "nr": 400, "br": 380,
+                "columns": [{ "name": "id", "total_values": 400 }],
+                "sortedColumn": { "name": "id", "total_values": 400 }
+            }
+        ]"#,
+    )
+    .unwrap()
+}
+
+#[test]
+fn report_serializes_the_predicate_candidates_and_winner() {
+    let predicate: Predicate = "c.id = Account.id".parse().unwrap();
+    let options = ReportOptions {
+        explain: true,
+        aliases: vec![(String::from("c"), String::from("Customer"))],
+        ..ReportOptions::default()
+    };
+    let report = join_report(&tables(), &predicate, &options).unwrap();
+    assert_eq!(report.best_method, JoinMethod::BlockNested);
+    assert_eq!(report.candidates.len(), JoinMethod::ALL.len());
+
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(json["best_method"], "Block Nested Join");
+    assert_eq!(json["best_cost"], 530);
+    assert_eq!(json["memory_size"], 10_000);
+    // The predicate is kept as entered, alias and all.
+    assert_eq!(json["predicate"][0]["left"]["table"], "c");
+    assert_eq!(json["candidates"][0]["explanation"], "Block Nested: br1 + br2 = 150 + 380 = 530 (fits in memory)");
+    assert_eq!(json["warnings"], serde_json::json!([]));
+    assert!(json.get("semi_join_cost").is_none());
+}
+
+#[test]
+fn report_carries_the_caveats_of_the_winner() {
+    let predicate: Predicate = "Customer.tier = Account.id".parse().unwrap();
+    let options = ReportOptions { memory_size: 20, method: Some(JoinMethod::Hash), ..ReportOptions::default() };
+    let report = join_report(&tables(), &predicate, &options).unwrap();
+    assert_eq!(report.costs(), vec![(JoinMethod::Hash, Some(report.best_cost))]);
+    // Each of Customer.tier's 2 values takes about 75 of Customer's 150 blocks.
+    assert_eq!(report.warnings.len(), 1);
+    assert!(report.warnings[0].contains("Customer.tier has only 2 distinct values"), "{}", report.warnings[0]);
+}
+
+#[test]
+fn io_cpu_model_charges_nested_loops_for_every_pair_of_tuples() {
+    let predicate: Predicate = "Customer.id = Account.id".parse().unwrap();
+    let nested = |cost_model: CostModel| {
+        let options = ReportOptions { method: Some(JoinMethod::NestedLoop), cost_model, ..ReportOptions::default() };
+        join_report(&tables(), &predicate, &options).unwrap().best_cost
+    };
+    // 200 * 400 comparisons at 0.001 blocks each.
+    assert_eq!(nested(CostModel::Io), 200 * 380 + 150);
+    assert_eq!(nested(CostModel::IoCpu), 200 * 380 + 150 + 80);
+
+    // Both relations fit in memory, so block nested loop wins on I/O alone,
+    // but merge join over the already sorted ids compares each tuple about once.
+    let io = join_report(&tables(), &predicate, &ReportOptions::default()).unwrap();
+    assert_eq!((io.best_method, io.best_cost), (JoinMethod::BlockNested, 530));
+    let options = ReportOptions { cost_model: CostModel::IoCpu, ..ReportOptions::default() };
+    let io_cpu = join_report(&tables(), &predicate, &options).unwrap();
+    assert_eq!((io_cpu.best_method, io_cpu.best_cost), (JoinMethod::Merge, 530 + 1));
+}
+
+#[test]
+fn memory_bytes_use_the_larger_block_size_and_warn_when_they_differ() {
+    let tables = parse_tables(
+        r#"[
+            { "name": "Small", "nr": 100, "tupleSize": 100, "blockBytes": 2048, "columns": [{ "name": "id", "total_values": 100 }] },
+            { "name": "Large", "nr": 100, "tupleSize": 100, "blockSize": 8192, "columns": [{ "name": "id", "total_values": 100 }] }
+        ]"#,
+    )
+    .unwrap();
+    // Both tables derive their block count from their own block size.
+    assert_eq!((tables[0].br, tables[1].br), (5, 2));
+
+    let predicate: Predicate = "Small.id = Large.id".parse().unwrap();
+    let options = ReportOptions { memory_bytes: Some(80 * 1024), ..ReportOptions::default() };
+    let report = join_report(&tables, &predicate, &options).unwrap();
+    assert_eq!(report.memory_size, 10);
+    assert!(report.warnings.iter().any(|x| x.contains("Small uses 2048-byte blocks but Large uses 8192-byte blocks")));
+}
+
+#[test]
+fn memory_bytes_below_the_minimum_are_raised_to_it() {
+    let predicate: Predicate = "Customer.id = Account.id".parse().unwrap();
+    let options = ReportOptions { memory_bytes: Some(8192), ..ReportOptions::default() };
+    let report = join_report(&tables(), &predicate, &options).unwrap();
+    assert_eq!(report.memory_size, 3);
+    assert!(report.warnings[0].contains("a memory size of 2 blocks is below the 3 every join method needs"));
+}
+
+#[test]
+fn reserved_buffers_are_checked_against_the_memory_of_each_join() {
+    let tables = parse_tables(
+        r#"[
+            { "name": "Small", "nr": 100, "br": 10, "blockBytes": 4096, "columns": [{ "name": "id", "total_values": 100 }] },
+            { "name": "Large", "nr": 100, "br": 10, "blockBytes": 16384, "columns": [{ "name": "id", "total_values": 100 }] }
+        ]"#,
+    )
+    .unwrap();
+    let predicate: Predicate = "Small.id = Large.id".parse().unwrap();
+    // 64K holds 16 blocks of 4 KiB but only 4 of the 16 KiB the join divides it by.
+    let options = ReportOptions { memory_bytes: Some(64 * 1024), reserved_buffers: 5, ..ReportOptions::default() };
+    match join_report(&tables, &predicate, &options) {
+        Err(JoinerError::Usage(msg)) => {
+            assert_eq!(msg, "--reserved-buffers (5) must be less than the memory size (4 blocks)")
+        }
+        other => panic!("expected a usage error, got {other:?}"),
+    }
+}
+
+#[test]
+fn assume_no_fit_applies_to_the_memory_of_each_join() {
+    let tables = parse_tables(
+        r#"[
+            { "name": "Small", "nr": 100, "br": 10, "blockBytes": 16384, "columns": [{ "name": "id", "total_values": 100 }] },
+            { "name": "Large", "nr": 100, "br": 10, "blockSize": 16384, "columns": [{ "name": "id", "total_values": 100 }] }
+        ]"#,
+    )
+    .unwrap();
+    let predicate: Predicate = "Small.id = Large.id".parse().unwrap();
+    let block_nested = |assume_no_fit| {
+        let options = ReportOptions {
+            memory_bytes: Some(64 * 1024),
+            method: Some(JoinMethod::BlockNested),
+            explain: true,
+            assume_no_fit,
+            ..ReportOptions::default()
+        };
+        join_report(&tables, &predicate, &options).unwrap().candidates.remove(0)
+    };
+
+    // The join gets 4 blocks of 16 KiB, two of them left for the outer.
+    assert_eq!(block_nested(false).cost, Some(10 + 5 * 10));
+    let no_fit = block_nested(true);
+    assert_eq!(no_fit.cost, Some(10 + 10 * 10));
+    assert!(no_fit.explanation.unwrap().ends_with("= 110 (outer Small assumed not to fit)"));
+}
@@ -0,0 +1,466 @@
+use joiner::{
+    check_distinct_counts, derive_block_count, deserialize_tables, estimate_best_join,
+    estimate_composite_join_cardinality, fold_case, format_schema, generate_metadata, load_all,
+    load_metadata, parse_alias, parse_predicate, parse_tables, resolve_aliases, resolve_join,
+    validation_issues, BuildSide, GenerateOptions, JoinMethod, JoinType, JoinerError,
+    SelectivityMode, Table, ValidationIssue, DEFAULT_RESERVED_BUFFERS,
+};
+use std::{env, fs, path::PathBuf, process};
+
+const CUSTOMER: &str = r#"[{
+    "name": "Customer", "nr": 200, "br": 150,
+    "columns": [
+        { "name": "id", "indexed": true, "total_values": 200 },
+        { "name": "name", "total_values": 150 }
+    ],
+    "sortedColumn": { "name": "id", "indexed": true, "total_values": 200 }
+}]"#;
+
+const ACCOUNT: &str = r#"[{
+    "name": "Account", "nr": 400, "br": 380,
+    "columns": [{ "name": "id", "total_values": 400 }],
+    "sortedColumn": { "name": "id", "total_values": 400 }
+}]"#;
+
+/// Writes `content` to a file in the temp directory unique to this test process.
+fn metadata_file(name: &str, content: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("joiner-{}-{name}", process::id()));
+    fs::write(&path, content).unwrap();
+    path
+}
+
+fn input_error(content: &str) -> String {
+    match parse_tables(content) {
+        Err(JoinerError::Input(msg)) => msg,
+        other => panic!("expected an input error, got {other:?}"),
+    }
+}
+
+#[test]
+fn parses_valid_metadata() {
+    let tables = parse_tables(CUSTOMER).unwrap();
+    assert_eq!(tables.len(), 1);
+    assert_eq!(tables[0].columns[1].name, "name");
+    assert!(!tables[0].columns[1].indexed);
+}
+
+#[test]
+fn rejects_metadata_without_tables() {
+    assert_eq!(input_error("[]"), "no tables in metadata");
+    assert_eq!(input_error("{}"), "no tables in metadata");
+}
+
+#[test]
+fn rejects_duplicate_column_names() {
+    let msg = input_error(
+        r#"[{
+            "name": "Customer", "nr": 200, "br": 150,
+            "columns": [
+                { "name": "id", "indexed": true, "total_values": 200 },
+                { "name": "id", "total_values": 150 }
+            ],
+            "sortedColumn": { "name": "id", "indexed": true, "total_values": 200 }
+        }]"#,
+    );
+    assert_eq!(msg, "duplicate column id in table Customer");
+}
+
+#[test]
+fn rejects_sorted_column_missing_from_columns() {
+    let msg = input_error(
+        r#"[{
+            "name": "Account", "nr": 400, "br": 380,
+            "columns": [
+                { "name": "id", "indexed": true, "total_values": 400 },
+                { "name": "balance", "total_values": 320 }
+            ],
+            "sortedColumn": { "name": "account_no", "total_values": 400 }
+        }]"#,
+    );
+    assert_eq!(msg, "table Account is sorted on account_no, which is not one of its columns");
+}
+
+#[test]
+fn resolve_join_reports_missing_table() {
+    let tables = parse_tables(CUSTOMER).unwrap();
+    let conditions = parse_predicate("Customer.id = Orders.cust_id").unwrap();
+    match resolve_join(&tables, &conditions) {
+        Err(JoinerError::TableNotFound(name)) => assert_eq!(name, "Orders"),
+        other => panic!("expected TableNotFound, got {other:?}"),
+    }
+}
+
+#[test]
+fn resolve_join_handles_self_joins() {
+    let tables = parse_tables(CUSTOMER).unwrap();
+    let conditions = parse_predicate("Customer.name = Customer.id").unwrap();
+    let join = resolve_join(&tables, &conditions).unwrap();
+    assert!(std::ptr::eq(join.table1, join.table2));
+    assert_eq!((join.keys[0].0.name.as_str(), join.keys[0].1.name.as_str()), ("name", "id"));
+
+    let estimate = estimate_best_join(&join, JoinType::Inner, 10_000, 256, BuildSide::Auto, DEFAULT_RESERVED_BUFFERS).unwrap();
+    assert_eq!((estimate.method, estimate.cost_blocks, estimate.estimated_rows), (JoinMethod::BlockNested, 300, 200));
+}
+
+#[test]
+fn aliased_predicate_resolves_to_the_aliased_tables() {
+    let mut tables = parse_tables(CUSTOMER).unwrap();
+    tables.extend(parse_tables(ACCOUNT).unwrap());
+    let aliases = [parse_alias("c=Customer").unwrap(), parse_alias("a = Account").unwrap()];
+    let conditions = parse_predicate("c.id = a.id").unwrap();
+
+    let conditions = resolve_aliases(&tables, &conditions, &aliases).unwrap();
+    let join = resolve_join(&tables, &conditions).unwrap();
+    assert_eq!((join.table1.name.as_str(), join.table2.name.as_str()), ("Customer", "Account"));
+}
+
+#[test]
+fn unknown_aliases_are_reported() {
+    let tables = parse_tables(CUSTOMER).unwrap();
+    let conditions = parse_predicate("c.id = x.id").unwrap();
+    let error = |aliases: &[(String, String)]| match resolve_aliases(&tables, &conditions, aliases) {
+        Err(JoinerError::Input(msg)) => msg,
+        other => panic!("expected an input error, got {other:?}"),
+    };
+
+    assert_eq!(error(&[parse_alias("c=Customer").unwrap()]), "unknown table or alias x");
+    assert_eq!(error(&[parse_alias("c=Orders").unwrap()]), "alias c refers to unknown table Orders");
+    assert!(parse_alias("Customer").is_err());
+}
+
+#[test]
+fn resolve_join_reports_missing_column() {
+    let tables = parse_tables(CUSTOMER).unwrap();
+    let conditions = parse_predicate("Customer.email = Customer.id").unwrap();
+    match resolve_join(&tables, &conditions) {
+        Err(JoinerError::ColumnNotFound { table, column, .. }) => {
+            assert_eq!((table.as_str(), column.as_str()), ("Customer", "email"))
+        }
+        other => panic!("expected ColumnNotFound, got {other:?}"),
+    }
+}
+
+#[test]
+fn missing_column_error_lists_the_available_columns() {
+    let tables = parse_tables(CUSTOMER).unwrap();
+    let conditions = parse_predicate("Customer.nmae = Customer.id").unwrap();
+    let err = resolve_join(&tables, &conditions).unwrap_err();
+    assert_eq!(err.to_string(), "column nmae not found in table Customer (available: id, name)");
+}
+
+#[test]
+fn counts_accept_scientific_and_underscore_notation() {
+    let metadata = |count: &str| format!(
+        r#"[{{
+            "name": "Orders", "nr": {count}, "br": {count},
+            "columns": [{{ "name": "id", "total_values": {count} }}],
+            "sortedColumn": {{ "name": "id", "total_values": {count} }}
+        }}]"#
+    );
+    for count in ["1500000", r#""1.5e6""#, r#""1_500_000""#, "1500000.2"] {
+        let table = &parse_tables(&metadata(count)).unwrap()[0];
+        assert_eq!((table.nr, table.br, table.columns[0].total_values), (1_500_000, 1_500_000, 1_500_000), "{count}");
+    }
+}
+
+#[test]
+fn counts_reject_text_that_is_not_a_number() {
+    let err = parse_tables(r#"[{
+        "name": "Orders", "nr": "lots", "br": 10,
+        "columns": [{ "name": "id", "total_values": 1 }],
+        "sortedColumn": { "name": "id", "total_values": 1 }
+    }]"#)
+    .unwrap_err();
+    assert!(err.to_string().contains("invalid count 'lots'"), "{err}");
+}
+
+#[test]
+fn explicit_br_is_kept() {
+    let tables = parse_tables(CUSTOMER).unwrap();
+    assert_eq!(tables[0].br, 150);
+}
+
+#[test]
+fn br_is_derived_from_tuple_and_block_size() {
+    let tables = parse_tables(
+        r#"[{
+            "name": "Orders", "nr": 1000, "tupleSize": 100, "blockSize": 4096,
+            "columns": [{ "name": "id", "total_values": 1000 }],
+            "sortedColumn": { "name": "id", "total_values": 1000 }
+        }]"#,
+    )
+    .unwrap();
+    // 40 tuples fit in a block, ceil(1000 / 40) = 25
+    assert_eq!(tables[0].br, 25);
+    assert_eq!(derive_block_count(1000, 100, 4096), 25);
+}
+
+#[test]
+fn block_count_requires_br_or_tuple_size() {
+    let err = parse_tables(
+        r#"[{
+            "name": "Orders", "nr": 1000,
+            "columns": [{ "name": "id", "total_values": 1000 }],
+            "sortedColumn": { "name": "id", "total_values": 1000 }
+        }]"#,
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("table Orders needs either br or tupleSize"), "{err}");
+}
+
+#[test]
+fn object_keyed_by_table_name_matches_array_layout() {
+    let from_array = parse_tables(CUSTOMER).unwrap();
+    let from_object = parse_tables(
+        r#"{
+            "Customer": {
+                "nr": 200, "br": 150,
+                "columns": [
+                    { "name": "id", "indexed": true, "total_values": 200 },
+                    { "name": "name", "total_values": 150 }
+                ],
+                "sortedColumn": { "name": "id", "indexed": true, "total_values": 200 }
+            }
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(from_object, from_array);
+}
+
+#[test]
+fn object_layout_reports_its_own_parse_error() {
+    let err = parse_tables(r#"{ "Customer": { "nr": 200 } }"#).unwrap_err();
+    assert!(err.to_string().contains("missing field `columns`"), "{err}");
+}
+
+const INCONSISTENT: &str = r#"[{
+    "name": "Account", "nr": 400, "br": 380,
+    "columns": [
+        { "name": "id", "indexed": true, "total_values": 400 },
+        { "name": "balance", "total_values": 500 }
+    ],
+    "sortedColumn": { "name": "id", "indexed": true, "total_values": 400 }
+}]"#;
+
+#[test]
+fn distinct_counts_above_row_count_are_clamped() {
+    let mut tables = parse_tables(INCONSISTENT).unwrap();
+    let warnings = check_distinct_counts(&mut tables, false).unwrap();
+    assert_eq!(
+        warnings,
+        vec!["column Account.balance has 500 distinct values but the table only has 400 rows"]
+    );
+    assert_eq!(tables[0].columns[1].total_values, 400);
+}
+
+#[test]
+fn distinct_counts_above_row_count_fail_in_strict_mode() {
+    let mut tables = parse_tables(INCONSISTENT).unwrap();
+    match check_distinct_counts(&mut tables, true) {
+        Err(JoinerError::Input(msg)) => {
+            assert_eq!(msg, "column Account.balance has 500 distinct values but the table only has 400 rows")
+        }
+        other => panic!("expected an input error, got {other:?}"),
+    }
+}
+
+#[test]
+fn load_all_merges_tables_from_every_file() {
+    let paths = [metadata_file("customer.json", CUSTOMER), metadata_file("account.json", ACCOUNT)];
+    let tables = load_all(&paths, SelectivityMode::Distinct).unwrap();
+    let names: Vec<&str> = tables.iter().map(|table| table.name.as_str()).collect();
+    assert_eq!(names, ["Customer", "Account"]);
+}
+
+#[test]
+fn load_all_rejects_a_table_defined_in_two_files() {
+    let paths = [metadata_file("first.json", CUSTOMER), metadata_file("second.json", CUSTOMER)];
+    match load_all(&paths, SelectivityMode::Distinct) {
+        Err(JoinerError::Input(msg)) => assert_eq!(
+            msg,
+            format!("table Customer is defined in both {} and {}", paths[0].display(), paths[1].display())
+        ),
+        other => panic!("expected an input error, got {other:?}"),
+    }
+}
+
+/// Customer and Account with every total_values written by `total_values`.
+fn customer_and_account(total_values: impl Fn(u32) -> String) -> String {
+    format!(
+        r#"[
+            {{ "name": "Customer", "nr": 200, "br": 150,
+               "columns": [{{ "name": "id", "indexed": true, "total_values": {c} }}],
+               "sortedColumn": {{ "name": "id", "indexed": true, "total_values": {c} }} }},
+            {{ "name": "Account", "nr": 400, "br": 380,
+               "columns": [{{ "name": "customer_id", "total_values": {a} }}],
+               "sortedColumn": {{ "name": "customer_id", "total_values": {a} }} }}
+        ]"#,
+        c = total_values(200),
+        a = total_values(160),
+    )
+}
+
+#[test]
+fn fraction_and_distinct_modes_give_the_same_cardinality() {
+    let distinct = metadata_file("distinct.json", &customer_and_account(|v| v.to_string()));
+    let fraction = metadata_file("fraction.json", &customer_and_account(|v| (1.0 / v as f64).to_string()));
+    let distinct = load_all(&[&distinct], SelectivityMode::Distinct).unwrap();
+    let fraction = load_all(&[&fraction], SelectivityMode::Fraction).unwrap();
+    assert_eq!(fraction, distinct);
+    assert_eq!(fraction[1].columns[0].total_values, 160);
+
+    let rows = |tables: &[Table]| {
+        let join = resolve_join(tables, &parse_predicate("Customer.id = Account.customer_id").unwrap()).unwrap();
+        estimate_composite_join_cardinality(join.table1, join.table2, &join.keys, JoinType::Inner)
+    };
+    // 200 * 400 / max(200, 160)
+    assert_eq!(rows(&fraction), 400);
+    assert_eq!(rows(&fraction), rows(&distinct));
+}
+
+#[test]
+fn fraction_mode_rejects_values_outside_zero_to_one() {
+    let path = metadata_file("bad-fraction.json", &customer_and_account(|v| v.to_string()));
+    match load_all(&[&path], SelectivityMode::Fraction) {
+        Err(JoinerError::Input(msg)) => assert_eq!(
+            msg,
+            "column Customer.id has total_values 200, which in fraction mode has to be greater than 0 and at most 1"
+        ),
+        other => panic!("expected an input error, got {other:?}"),
+    }
+}
+
+#[test]
+fn malformed_file_names_its_path_and_location() {
+    let path = metadata_file("malformed.json", "[{\n    \"name\": \"Customer\",\n    \"nr\": 200,,\n}]");
+    let err = load_all(&[&path], SelectivityMode::Distinct).unwrap_err();
+    assert!(matches!(err, JoinerError::Parse { .. }), "{err:?}");
+    assert_eq!(
+        err.to_string(),
+        format!("parse error in {} at line 3, column 15: key must be a string", path.display())
+    );
+}
+
+#[test]
+fn malformed_table_names_its_index_in_the_array() {
+    let table = |name: &str, br: &str| {
+        format!(
+            r#"{{ "name": "{name}", "nr": 10, {br}"columns": [{{ "name": "id", "total_values": 10 }}], "sortedColumn": {{ "name": "id", "total_values": 10 }} }}"#
+        )
+    };
+    let content = format!("[{}, {}, {}]", table("A", r#""br": 1, "#), table("B", r#""br": 1, "#), table("C", ""));
+    match deserialize_tables(&content) {
+        Err(JoinerError::Parse { table, .. }) => assert_eq!(table.as_deref(), Some("the table at index 2 (C)")),
+        other => panic!("expected a parse error, got {other:?}"),
+    }
+
+    let path = metadata_file("third-without-br.json", &content);
+    let err = load_all(&[&path], SelectivityMode::Distinct).unwrap_err();
+    assert!(err.to_string().contains(", in the table at index 2 (C): table C needs either br or tupleSize"), "{err}");
+}
+
+#[test]
+fn validation_reports_every_issue() {
+    let tables = deserialize_tables(
+        r#"[
+            {
+                "name": "Customer", "nr": 200, "br": 0,
+                "columns": [
+                    { "name": "id", "total_values": 200 },
+                    { "name": "id", "total_values": 250 }
+                ],
+                "sortedColumn": { "name": "name", "total_values": 200 }
+            },
+            {
+                "name": "Customer", "nr": 10, "br": 1,
+                "columns": [{ "name": "id", "total_values": 10 }],
+                "sortedColumn": { "name": "id", "total_values": 10 }
+            }
+        ]"#,
+    )
+    .unwrap();
+    let customer = || String::from("Customer");
+    assert_eq!(
+        validation_issues(&tables),
+        vec![
+            ValidationIssue::DuplicateColumn { table: customer(), column: String::from("id") },
+            ValidationIssue::SortedColumnMissing { table: customer(), column: String::from("name") },
+            ValidationIssue::TooManyDistinctValues {
+                table: customer(),
+                column: String::from("id"),
+                total_values: 250,
+                rows: 200,
+            },
+            ValidationIssue::NoBlocks(customer()),
+            ValidationIssue::DuplicateTable(customer()),
+        ]
+    );
+}
+
+#[test]
+fn names_differing_by_case_only_match_after_folding() {
+    let predicate = "customer.NAME = CUSTOMER.id";
+    let mut tables = parse_tables(CUSTOMER).unwrap();
+    let conditions = parse_predicate(predicate).unwrap();
+    match resolve_join(&tables, &conditions) {
+        Err(JoinerError::TableNotFound(name)) => assert_eq!(name, "customer"),
+        other => panic!("expected TableNotFound, got {other:?}"),
+    }
+
+    fold_case(&mut tables);
+    let conditions = parse_predicate(&predicate.to_lowercase()).unwrap();
+    let join = resolve_join(&tables, &conditions).unwrap();
+    assert_eq!((join.table1.name.as_str(), join.keys[0].0.name.as_str()), ("customer", "name"));
+}
+
+#[test]
+fn schema_is_formatted_as_aligned_tables() {
+    let mut tables = parse_tables(CUSTOMER).unwrap();
+    tables.extend(parse_tables(ACCOUNT).unwrap());
+    assert_eq!(
+        format_schema(&tables),
+        "Customer (nr = 200, br = 150)\n\
+         Name  Indexed  TotalValues  Sorted\n\
+         id    yes              200  *\n\
+         name  no               150\n\
+         \n\
+         Account (nr = 400, br = 380)\n\
+         Name  Indexed  TotalValues  Sorted\n\
+         id    no               400  *\n\
+         \n"
+    );
+}
+
+#[test]
+fn gzipped_metadata_loads_like_plain_metadata() {
+    let fixtures = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let plain = load_metadata(fixtures.join("metadata.json"), SelectivityMode::Distinct).unwrap();
+    let gzipped = load_metadata(fixtures.join("metadata.json.gz"), SelectivityMode::Distinct).unwrap();
+    assert_eq!(plain.len(), 2);
+    assert_eq!(gzipped, plain);
+}
+
+#[test]
+fn yaml_metadata_loads_like_json_metadata() {
+    let fixtures = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let json = load_metadata(fixtures.join("metadata.json"), SelectivityMode::Distinct).unwrap();
+    let yaml = load_metadata(fixtures.join("metadata.yaml"), SelectivityMode::Distinct).unwrap();
+    assert_eq!(yaml, json);
+}
+
+#[test]
+fn generated_metadata_round_trips_through_load_metadata() {
+    let options = GenerateOptions { tables: 12, max_columns: 6, max_rows: 1_000_000 };
+    let metadata = generate_metadata(&options, 42);
+    let path = metadata_file("generated.json", &serde_json::to_string(&metadata).unwrap());
+
+    let tables = load_metadata(&path, SelectivityMode::Distinct).unwrap();
+    assert_eq!(tables.len(), 12);
+    for table in &tables {
+        assert!((1..=6).contains(&table.columns.len()));
+        assert!(table.nr <= 1_000_000 && table.br > 0);
+        assert!(table.columns.iter().all(|x| x.total_values <= table.nr));
+    }
+    assert_eq!(generate_metadata(&options, 42), metadata);
+}
+